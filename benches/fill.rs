@@ -0,0 +1,22 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use sight::{Color, Rect, Sight};
+
+fn fill(c: &mut Criterion) {
+    let mut sight = Sight::headless(1024, 768);
+
+    c.bench_function("clear", |b| b.iter(|| sight.clear(black_box(Color::BLUE))));
+    c.bench_function("fill_rect opaque", |b| {
+        b.iter(|| sight.fill_rect(black_box(Rect::new(100, 100, 640, 480)), Color::RED))
+    });
+    c.bench_function("fill_rect translucent", |b| {
+        b.iter(|| {
+            sight.fill_rect(
+                black_box(Rect::new(100, 100, 640, 480)),
+                Color::RED.with_alpha(128),
+            )
+        })
+    });
+}
+
+criterion_group!(benches, fill);
+criterion_main!(benches);
@@ -1,3 +1,5 @@
+use crate::bmp::BmpImage;
+use crate::{Color, Rect};
 use alloc::collections::BTreeMap;
 use alloc::string::String;
 use alloc::vec::Vec;
@@ -16,43 +18,84 @@ pub struct Glyph {
     pub offset_x: i32,
     pub offset_y: i32,
     pub device_width: u32,
+    pub scalable_width: u32,
 }
 
 impl Glyph {
-    pub fn draw<F>(&self, mut x: i32, mut y: i32, mut set_pixel: F)
+    /// Draws the bitmap with its top-left corner at `(x + offset_x, y + offset_y)`.
+    pub fn draw<F>(&self, x: i32, y: i32, set_pixel: F)
     where
         F: FnMut(i32, i32),
     {
-        x += self.offset_x;
-        y += self.offset_y;
+        self.draw_bitmap(x + self.offset_x, y + self.offset_y, set_pixel);
+    }
 
-        let bytes_per_row = ((self.width + 7) / 8) as usize;
+    /// Draws the glyph with its origin on the baseline at `(x, baseline)`, so descenders
+    /// (negative `offset_y`) extend below it.
+    pub fn draw_on_baseline<F>(&self, x: i32, baseline: i32, set_pixel: F)
+    where
+        F: FnMut(i32, i32),
+    {
+        let top = baseline - self.offset_y - self.height as i32;
+        self.draw_bitmap(x + self.offset_x, top, set_pixel);
+    }
 
+    fn draw_bitmap<F>(&self, x: i32, y: i32, mut set_pixel: F)
+    where
+        F: FnMut(i32, i32),
+    {
         for row in 0..self.height {
-            let row_offset = row as usize * bytes_per_row;
-
             for col in 0..self.width {
-                let byte_index = row_offset + (col / 8) as usize;
-                let bit_index = 7 - (col % 8);
-
-                if byte_index < self.bitmap.len() {
-                    let byte = self.bitmap[byte_index];
-                    if (byte >> bit_index) & 1 == 1 {
-                        set_pixel(x + col as i32, y + row as i32);
-                    }
+                if self.is_set(col, row) {
+                    set_pixel(x + col as i32, y + row as i32);
                 }
             }
         }
     }
+
+    pub fn to_image(&self, fg: Color, bg: Color) -> BmpImage {
+        let mut image = BmpImage::new(self.width, self.height);
+
+        for row in 0..self.height {
+            for col in 0..self.width {
+                let color = if self.is_set(col, row) { fg } else { bg };
+                image.set_pixel(col, row, color);
+            }
+        }
+
+        image
+    }
+
+    fn bytes_per_row(&self) -> usize {
+        self.width.div_ceil(8) as usize
+    }
+
+    fn is_set(&self, col: u32, row: u32) -> bool {
+        let bytes_per_row = self.bytes_per_row();
+        if row >= self.height || col >= self.width {
+            return false;
+        }
+
+        let byte_index = row as usize * bytes_per_row + (col / 8) as usize;
+        let bit_index = 7 - (col % 8);
+
+        match self.bitmap.get(byte_index) {
+            Some(byte) => (byte >> bit_index) & 1 == 1,
+            None => false,
+        }
+    }
 }
 
 #[derive(Debug)]
 pub struct Font {
     pub font_type: FontType,
     pub font_name: String,
+    pub version: String,
+    pub content_version: Option<u32>,
     pub size: u32,
     pub bounding_box: (u32, u32, i32, i32),
     pub glyphs: BTreeMap<u32, Glyph>,
+    pub fallback: Option<char>,
 }
 
 impl Font {
@@ -60,9 +103,12 @@ impl Font {
         Self {
             font_type,
             font_name,
+            version: String::new(),
+            content_version: None,
             size: 0,
             bounding_box: (0, 0, 0, 0),
             glyphs: BTreeMap::new(),
+            fallback: None,
         }
     }
 
@@ -70,32 +116,100 @@ impl Font {
         self.glyphs.get(&(ch as u32))
     }
 
+    pub fn has_glyph(&self, ch: char) -> bool {
+        self.glyphs.contains_key(&(ch as u32))
+    }
+
+    pub fn set_fallback(&mut self, ch: char) {
+        self.fallback = Some(ch);
+    }
+
+    fn glyph_for(&self, ch: char) -> Option<&Glyph> {
+        self.get_glyph(ch)
+            .or_else(|| self.fallback.and_then(|fallback| self.get_glyph(fallback)))
+    }
+
+    pub fn get_min_offsets(&self) -> (i32, i32) {
+        let mut glyphs = self.glyphs.values();
+        let Some(first) = glyphs.next() else {
+            return (0, 0);
+        };
+
+        glyphs.fold((first.offset_x, first.offset_y), |(min_x, min_y), glyph| {
+            (min_x.min(glyph.offset_x), min_y.min(glyph.offset_y))
+        })
+    }
+
+    pub fn baseline_offset(&self) -> i32 {
+        let (_, min_y) = self.get_min_offsets();
+        self.text_height() as i32 + min_y.min(0)
+    }
+
     pub fn draw_char<F>(&self, ch: char, x: i32, y: i32, set_pixel: F) -> i32
     where
         F: FnMut(i32, i32),
     {
-        if let Some(glyph) = self.get_glyph(ch) {
-            glyph.draw(x, y, set_pixel);
+        self.draw_char_on_baseline(ch, x, y + self.baseline_offset(), set_pixel)
+    }
+
+    fn draw_char_on_baseline<F>(&self, ch: char, x: i32, baseline: i32, set_pixel: F) -> i32
+    where
+        F: FnMut(i32, i32),
+    {
+        if let Some(glyph) = self.glyph_for(ch) {
+            glyph.draw_on_baseline(x, baseline, set_pixel);
             glyph.device_width as i32
         } else {
             self.bounding_box.0 as i32
         }
     }
 
-    pub fn draw_text<F>(&self, text: &str, mut x: i32, y: i32, mut set_pixel: F)
+    pub fn draw_text<F>(&self, text: &str, x: i32, y: i32, set_pixel: F) -> (i32, i32)
     where
         F: FnMut(i32, i32),
     {
+        self.draw_text_at(text, (x, y), set_pixel)
+    }
+
+    pub fn draw_text_at<F>(&self, text: &str, cursor: (i32, i32), mut set_pixel: F) -> (i32, i32)
+    where
+        F: FnMut(i32, i32),
+    {
+        let (mut x, y) = cursor;
+        let baseline = y + self.baseline_offset();
         for ch in text.chars() {
-            let advance = self.draw_char(ch, x, y, &mut set_pixel);
+            let advance = self.draw_char_on_baseline(ch, x, baseline, &mut set_pixel);
             x += advance;
         }
+        (x, y)
+    }
+
+    pub fn draw_text_vertical<F>(&self, text: &str, x: i32, y: i32, mut set_pixel: F) -> (i32, i32)
+    where
+        F: FnMut(i32, i32),
+    {
+        let line_height = self.text_height() as i32;
+        let column_width = self.bounding_box.0 as i32;
+        let baseline_offset = self.baseline_offset();
+        let (mut pen_x, mut pen_y) = (x, y);
+
+        for ch in text.chars() {
+            if ch == '\n' {
+                pen_x -= column_width;
+                pen_y = y;
+                continue;
+            }
+            self.draw_char_on_baseline(ch, pen_x, pen_y + baseline_offset, &mut set_pixel);
+            pen_y += line_height;
+        }
+
+        (pen_x, pen_y)
     }
 
     pub fn text_width(&self, text: &str) -> u32 {
         let mut width = 0;
         for ch in text.chars() {
-            if let Some(glyph) = self.get_glyph(ch) {
+            if let Some(glyph) = self.glyph_for(ch) {
                 width += glyph.device_width;
             } else {
                 width += self.bounding_box.0;
@@ -104,18 +218,76 @@ impl Font {
         width
     }
 
+    pub fn text_ink_bounds(&self, text: &str) -> Rect {
+        let baseline = self.baseline_offset();
+        let mut pen_x = 0;
+        let mut bounds: Option<(i32, i32, i32, i32)> = None;
+
+        for ch in text.chars() {
+            let Some(glyph) = self.glyph_for(ch) else {
+                pen_x += self.bounding_box.0 as i32;
+                continue;
+            };
+
+            if glyph.width > 0 && glyph.height > 0 {
+                let left = pen_x + glyph.offset_x;
+                let top = baseline - glyph.offset_y - glyph.height as i32;
+                let right = left + glyph.width as i32;
+                let bottom = top + glyph.height as i32;
+                bounds = Some(match bounds {
+                    Some((x1, y1, x2, y2)) => {
+                        (x1.min(left), y1.min(top), x2.max(right), y2.max(bottom))
+                    }
+                    None => (left, top, right, bottom),
+                });
+            }
+            pen_x += glyph.device_width as i32;
+        }
+
+        match bounds {
+            Some((x1, y1, x2, y2)) => Rect::new(x1, y1, (x2 - x1) as u32, (y2 - y1) as u32),
+            None => Rect::new(0, 0, 0, 0),
+        }
+    }
+
+    pub fn scaled_advance(&self, ch: char, point_size: f32, dpi: f32) -> f32 {
+        match self.glyph_for(ch) {
+            Some(glyph) if glyph.scalable_width > 0 => {
+                glyph.scalable_width as f32 / 1000.0 * point_size * dpi / 72.0
+            }
+            Some(glyph) => glyph.device_width as f32,
+            None => self.bounding_box.0 as f32,
+        }
+    }
+
     pub fn text_height(&self) -> u32 {
         self.bounding_box.1
     }
+
+    pub fn text_width_vertical(&self, text: &str) -> u32 {
+        text.split('\n').count() as u32 * self.bounding_box.0
+    }
+
+    pub fn text_height_vertical(&self, text: &str) -> u32 {
+        let rows = text
+            .split('\n')
+            .map(|column| column.chars().count() as u32)
+            .max()
+            .unwrap_or(0);
+        rows * self.text_height()
+    }
 }
 
 pub fn parse_bdf_font(data: &[u8]) -> Result<Font, &'static str> {
     let mut font = Font {
         font_type: FontType::BDF,
         font_name: String::new(),
+        version: String::new(),
+        content_version: None,
         size: 0,
         bounding_box: (0, 0, 0, 0),
         glyphs: BTreeMap::new(),
+        fallback: None,
     };
 
     let mut current_glyph: Option<Glyph> = None;
@@ -156,12 +328,16 @@ fn parse_line(
     in_bitmap: &mut bool,
     bitmap_data: &mut Vec<u8>,
 ) {
-    if line.starts_with("FONT ") {
-        font.font_name = line[5..].trim().into();
-    } else if line.starts_with("SIZE ") {
-        font.size = line[5..].trim().parse().unwrap_or(0);
-    } else if line.starts_with("FONTBOUNDINGBOX ") {
-        let parts: Vec<_> = line[16..].split_whitespace().collect();
+    if let Some(rest) = line.strip_prefix("STARTFONT ") {
+        font.version = rest.trim().into();
+    } else if let Some(rest) = line.strip_prefix("CONTENTVERSION ") {
+        font.content_version = rest.trim().parse().ok();
+    } else if let Some(rest) = line.strip_prefix("FONT ") {
+        font.font_name = rest.trim().into();
+    } else if let Some(rest) = line.strip_prefix("SIZE ") {
+        font.size = rest.trim().parse().unwrap_or(0);
+    } else if let Some(rest) = line.strip_prefix("FONTBOUNDINGBOX ") {
+        let parts: Vec<_> = rest.split_whitespace().collect();
         if parts.len() >= 4 {
             font.bounding_box = (
                 parts[0].parse().unwrap_or(0),
@@ -179,21 +355,29 @@ fn parse_line(
             offset_x: 0,
             offset_y: 0,
             device_width: 0,
+            scalable_width: 0,
         });
-    } else if line.starts_with("ENCODING ") {
+    } else if let Some(rest) = line.strip_prefix("ENCODING ") {
+        if let Some(ref mut glyph) = current_glyph {
+            glyph.encoding = rest.trim().parse().unwrap_or(0);
+        }
+    } else if let Some(rest) = line.strip_prefix("SWIDTH ") {
         if let Some(ref mut glyph) = current_glyph {
-            glyph.encoding = line[9..].trim().parse().unwrap_or(0);
+            let parts: Vec<_> = rest.split_whitespace().collect();
+            if !parts.is_empty() {
+                glyph.scalable_width = parts[0].parse().unwrap_or(0);
+            }
         }
-    } else if line.starts_with("DWIDTH ") {
+    } else if let Some(rest) = line.strip_prefix("DWIDTH ") {
         if let Some(ref mut glyph) = current_glyph {
-            let parts: Vec<_> = line[7..].split_whitespace().collect();
+            let parts: Vec<_> = rest.split_whitespace().collect();
             if !parts.is_empty() {
                 glyph.device_width = parts[0].parse().unwrap_or(0);
             }
         }
-    } else if line.starts_with("BBX ") {
+    } else if let Some(rest) = line.strip_prefix("BBX ") {
         if let Some(ref mut glyph) = current_glyph {
-            let parts: Vec<_> = line[4..].split_whitespace().collect();
+            let parts: Vec<_> = rest.split_whitespace().collect();
             if parts.len() >= 4 {
                 glyph.width = parts[0].parse().unwrap_or(0);
                 glyph.height = parts[1].parse().unwrap_or(0);
@@ -208,17 +392,219 @@ fn parse_line(
         if let Some(mut glyph) = current_glyph.take() {
             if glyph.encoding < 256 {
                 glyph.bitmap = core::mem::take(bitmap_data);
+                glyph
+                    .bitmap
+                    .resize(glyph.height as usize * glyph.bytes_per_row(), 0);
                 font.glyphs.insert(glyph.encoding, glyph);
             }
         }
         *in_bitmap = false;
     } else if *in_bitmap {
+        let Some(ref glyph) = current_glyph else {
+            return;
+        };
+
         let hex_str = line.trim();
+        let mut row = Vec::with_capacity(glyph.bytes_per_row());
         for i in (0..hex_str.len()).step_by(2) {
             let end = (i + 2).min(hex_str.len());
-            if let Ok(byte) = u8::from_str_radix(&hex_str[i..end], 16) {
-                bitmap_data.push(byte);
+            let byte = hex_str
+                .get(i..end)
+                .and_then(|pair| u8::from_str_radix(pair, 16).ok())
+                .unwrap_or(0);
+            row.push(byte);
+        }
+        row.resize(glyph.bytes_per_row(), 0);
+        bitmap_data.extend_from_slice(&row);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FONT: &[u8] = b"STARTFONT 2.1
+FONT test
+SIZE 8 75 75
+FONTBOUNDINGBOX 6 10 0 -2
+STARTCHAR A
+ENCODING 65
+SWIDTH 500 0
+DWIDTH 6 0
+BBX 5 7 0 0
+BITMAP
+20
+50
+88
+F8
+88
+88
+88
+ENDCHAR
+STARTCHAR g
+ENCODING 103
+SWIDTH 500 0
+DWIDTH 6 0
+BBX 5 7 0 -2
+BITMAP
+78
+88
+88
+78
+08
+08
+70
+ENDCHAR
+ENDFONT
+";
+
+    fn pixels(font: &Font, text: &str) -> Vec<(i32, i32)> {
+        let mut pixels = Vec::new();
+        font.draw_text(text, 0, 0, |x, y| pixels.push((x, y)));
+        pixels
+    }
+
+    #[test]
+    fn draw_text_continues_from_cursor() {
+        let font = parse_bdf_font(FONT).unwrap();
+        let mut split = Vec::new();
+        let cursor = font.draw_text("Ag", 3, 4, |x, y| split.push((x, y)));
+        assert_eq!(cursor, (15, 4));
+        let end = font.draw_text_at("A", cursor, |x, y| split.push((x, y)));
+
+        let mut whole = Vec::new();
+        assert_eq!(font.draw_text("AgA", 3, 4, |x, y| whole.push((x, y))), end);
+        assert_eq!(split, whole);
+    }
+
+    #[test]
+    fn vertical_text_stacks_rows() {
+        let font = parse_bdf_font(FONT).unwrap();
+        let mut single = Vec::new();
+        font.draw_char('A', 2, 0, |x, y| single.push((x, y)));
+
+        let mut stacked = Vec::new();
+        let end = font.draw_text_vertical("AAA", 2, 0, |x, y| stacked.push((x, y)));
+        assert_eq!(end, (2, 30));
+        assert_eq!(font.text_height_vertical("AAA"), 30);
+
+        let expected: Vec<(i32, i32)> = (0..3)
+            .flat_map(|row| single.iter().map(move |&(x, y)| (x, y + row * 10)))
+            .collect();
+        assert_eq!(stacked, expected);
+    }
+
+    #[test]
+    fn glyphs_share_a_baseline() {
+        let font = parse_bdf_font(FONT).unwrap();
+        assert_eq!(font.get_min_offsets(), (0, -2));
+        assert_eq!(font.baseline_offset(), 8);
+
+        let bottom = |text| pixels(&font, text).iter().map(|&(_, y)| y).max().unwrap();
+        assert_eq!(bottom("A"), 7);
+        assert_eq!(bottom("g"), 9);
+        assert_eq!(pixels(&font, "Ag").iter().map(|&(_, y)| y).max(), Some(9));
+    }
+
+    #[test]
+    fn glyph_image_matches_bitmap() {
+        let font = parse_bdf_font(FONT).unwrap();
+        let glyph = font.get_glyph('A').unwrap();
+        let image = glyph.to_image(Color::WHITE, Color::BLACK);
+        assert_eq!((image.width, image.height), (5, 7));
+
+        for (row, byte) in glyph.bitmap.iter().enumerate() {
+            for col in 0..5 {
+                let set = byte & (0x80 >> col) != 0;
+                let expected = if set { Color::WHITE } else { Color::BLACK };
+                assert_eq!(image.get_pixel(col, row as u32), Some(expected));
             }
         }
+        assert_eq!(image.get_pixel(2, 0), Some(Color::WHITE));
+        assert_eq!(image.get_pixel(0, 0), Some(Color::BLACK));
+    }
+
+    #[test]
+    fn short_bitmap_rows_are_padded() {
+        let font = parse_bdf_font(
+            b"FONT t\nFONTBOUNDINGBOX 8 4 0 0\nSTARTCHAR A\nENCODING 65\nDWIDTH 8 0\n\
+              BBX 12 4 0 0\nBITMAP\nF\n\xc3\xa9\nENDCHAR\n",
+        )
+        .unwrap();
+        let glyph = font.get_glyph('A').unwrap();
+        assert_eq!(glyph.bitmap.len(), 8);
+
+        let mut lit = Vec::new();
+        glyph.draw_on_baseline(0, 4, |x, y| lit.push((x, y)));
+        assert_eq!(lit.len(), 4);
+        assert!(lit.iter().all(|&(_, y)| y == 0));
+    }
+
+    #[test]
+    fn parses_version_headers() {
+        let font = parse_bdf_font(b"STARTFONT 2.1\nCONTENTVERSION 7\nFONT x\n").unwrap();
+        assert_eq!(font.version, "2.1");
+        assert_eq!(font.content_version, Some(7));
+        assert_eq!(font.font_name, "x");
+
+        let font = parse_bdf_font(FONT).unwrap();
+        assert_eq!(font.version, "2.1");
+        assert_eq!(font.content_version, None);
+    }
+
+    #[test]
+    fn ink_bounds_match_drawn_pixels() {
+        let font = parse_bdf_font(FONT).unwrap();
+        for text in ["A", "g", "Ag", "gAg"] {
+            let drawn = pixels(&font, text);
+            let min_x = drawn.iter().map(|p| p.0).min().unwrap();
+            let max_x = drawn.iter().map(|p| p.0).max().unwrap();
+            let min_y = drawn.iter().map(|p| p.1).min().unwrap();
+            let max_y = drawn.iter().map(|p| p.1).max().unwrap();
+            let expected = Rect::new(
+                min_x,
+                min_y,
+                (max_x - min_x + 1) as u32,
+                (max_y - min_y + 1) as u32,
+            );
+            assert_eq!(font.text_ink_bounds(text), expected, "{text}");
+        }
+        assert_eq!(font.text_ink_bounds(""), Rect::new(0, 0, 0, 0));
+    }
+
+    #[test]
+    fn scaled_advance_uses_swidth() {
+        let font = parse_bdf_font(FONT).unwrap();
+        assert_eq!(font.get_glyph('A').unwrap().scalable_width, 500);
+        assert!((font.scaled_advance('A', 12.0, 144.0) - 12.0).abs() < 1e-4);
+        assert!((font.scaled_advance('g', 10.0, 72.0) - 5.0).abs() < 1e-4);
+        assert_eq!(font.scaled_advance('Z', 12.0, 144.0), 6.0);
+    }
+
+    #[test]
+    fn missing_glyph_uses_fallback() {
+        let mut font = parse_bdf_font(FONT).unwrap();
+        assert!(font.has_glyph('A'));
+        assert!(!font.has_glyph('Z'));
+        assert!(pixels(&font, "Z").is_empty());
+        assert_eq!(font.text_width("Z"), 6);
+
+        font.set_fallback('A');
+        assert_eq!(pixels(&font, "Z"), pixels(&font, "A"));
+        assert_eq!(font.text_ink_bounds("Z"), font.text_ink_bounds("A"));
+        assert!(!font.has_glyph('Z'));
+    }
+
+    #[test]
+    fn glyph_draw_keeps_top_left_origin() {
+        let font = parse_bdf_font(FONT).unwrap();
+        let glyph = font.get_glyph('g').unwrap();
+        let mut top_left = Vec::new();
+        glyph.draw(3, 5, |x, y| top_left.push((x, y)));
+        assert_eq!(top_left.iter().map(|p| p.1).min(), Some(3));
+
+        let mut on_baseline = Vec::new();
+        glyph.draw_on_baseline(3, 5, |x, y| on_baseline.push((x, y)));
+        assert_eq!(on_baseline.iter().map(|p| p.1).max(), Some(6));
     }
 }
@@ -1,4 +1,5 @@
 extern crate alloc;
+use crate::{Color, Drawable};
 use alloc::vec::Vec;
 
 pub struct BmpImage {
@@ -7,7 +8,192 @@ pub struct BmpImage {
     pub data: Vec<u8>,
 }
 
+impl Drawable for BmpImage {
+    fn width(&self) -> u32 {
+        self.width
+    }
+
+    fn height(&self) -> u32 {
+        self.height
+    }
+
+    fn pixel(&self, x: u32, y: u32) -> Color {
+        self.get_pixel(x, y).unwrap_or(Color::TRANSPARENT)
+    }
+}
+
 impl BmpImage {
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {
+            width,
+            height,
+            data: alloc::vec![0; (width * height * 4) as usize],
+        }
+    }
+
+    pub fn get_pixel(&self, x: u32, y: u32) -> Option<Color> {
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+
+        let idx = ((y * self.width + x) * 4) as usize;
+        let pixel = self.data.get(idx..idx + 4)?;
+        Some(Color::rgba(pixel[2], pixel[1], pixel[0], pixel[3]))
+    }
+
+    pub fn set_pixel(&mut self, x: u32, y: u32, color: Color) {
+        if x >= self.width || y >= self.height {
+            return;
+        }
+
+        let idx = ((y * self.width + x) * 4) as usize;
+        if let Some(pixel) = self.data.get_mut(idx..idx + 4) {
+            pixel.copy_from_slice(&[color.b, color.g, color.r, color.a]);
+        }
+    }
+
+    pub fn to_grayscale(&self) -> BmpImage {
+        let mut data = self.data.clone();
+        for pixel in data.chunks_exact_mut(4) {
+            let gray = Color::rgb(pixel[2], pixel[1], pixel[0]).luminance();
+            pixel[..3].fill(gray);
+        }
+
+        BmpImage {
+            width: self.width,
+            height: self.height,
+            data,
+        }
+    }
+
+    pub fn tint(&self, color: Color) -> BmpImage {
+        let factors = [color.b, color.g, color.r, color.a];
+        let mut data = self.data.clone();
+        for pixel in data.chunks_exact_mut(4) {
+            for (channel, factor) in pixel.iter_mut().zip(factors) {
+                *channel = (*channel as u32 * factor as u32 / 255) as u8;
+            }
+        }
+
+        BmpImage {
+            width: self.width,
+            height: self.height,
+            data,
+        }
+    }
+
+    pub fn palette(&self, max_colors: usize) -> Vec<Color> {
+        if max_colors == 0 {
+            return Vec::new();
+        }
+
+        let mut colors = Vec::with_capacity((self.width * self.height) as usize);
+        for y in 0..self.height {
+            for x in 0..self.width {
+                if let Some(color) = self.get_pixel(x, y).filter(|c| c.a > 0) {
+                    colors.push(color);
+                }
+            }
+        }
+        colors.sort_unstable_by_key(|c| c.to_u32());
+
+        let mut unique = colors.clone();
+        unique.dedup();
+        if unique.len() <= max_colors {
+            return unique;
+        }
+
+        let mut buckets = alloc::vec![colors];
+        while buckets.len() < max_colors {
+            let Some((index, channel, range)) = buckets
+                .iter()
+                .enumerate()
+                .map(|(i, bucket)| {
+                    let (channel, range) = widest_channel(bucket);
+                    (i, channel, range)
+                })
+                .max_by_key(|&(_, _, range)| range)
+            else {
+                break;
+            };
+            if range == 0 {
+                break;
+            }
+
+            let mut bucket = buckets.swap_remove(index);
+            bucket.sort_unstable_by_key(|&c| channel_value(c, channel));
+
+            let mid = bucket.len() / 2;
+            let differs = |i: &usize| {
+                channel_value(bucket[i - 1], channel) != channel_value(bucket[*i], channel)
+            };
+            let split = (mid..bucket.len())
+                .find(differs)
+                .or_else(|| (1..mid).rev().find(differs))
+                .unwrap_or(mid);
+
+            let upper = bucket.split_off(split);
+            buckets.push(bucket);
+            buckets.push(upper);
+        }
+
+        buckets
+            .iter()
+            .map(|bucket| Color::average(bucket))
+            .collect()
+    }
+
+    pub fn downscale_averaged(
+        &self,
+        new_width: u32,
+        new_height: u32,
+    ) -> Result<Self, &'static str> {
+        if new_width == 0 || new_height == 0 {
+            return Err("Invalid dimensions");
+        }
+        if new_width > self.width || new_height > self.height {
+            return Err("Upscaling not supported");
+        }
+
+        let mut scaled = BmpImage::new(new_width, new_height);
+
+        for dy in 0..new_height {
+            let sy1 = (dy as u64 * self.height as u64 / new_height as u64) as u32;
+            let sy2 = ((dy as u64 + 1) * self.height as u64 / new_height as u64) as u32;
+
+            for dx in 0..new_width {
+                let sx1 = (dx as u64 * self.width as u64 / new_width as u64) as u32;
+                let sx2 = ((dx as u64 + 1) * self.width as u64 / new_width as u64) as u32;
+
+                let (mut r, mut g, mut b, mut a) = (0u64, 0u64, 0u64, 0u64);
+                let mut count = 0u64;
+
+                for sy in sy1..sy2.max(sy1 + 1) {
+                    for sx in sx1..sx2.max(sx1 + 1) {
+                        if let Some(color) = self.get_pixel(sx, sy) {
+                            let alpha = color.a as u64;
+                            r += color.r as u64 * alpha;
+                            g += color.g as u64 * alpha;
+                            b += color.b as u64 * alpha;
+                            a += alpha;
+                            count += 1;
+                        }
+                    }
+                }
+
+                let color = match (r.checked_div(a), g.checked_div(a), b.checked_div(a)) {
+                    (Some(r), Some(g), Some(b)) => {
+                        Color::rgba(r as u8, g as u8, b as u8, (a / count) as u8)
+                    }
+                    _ => Color::TRANSPARENT,
+                };
+                scaled.set_pixel(dx, dy, color);
+            }
+        }
+
+        Ok(scaled)
+    }
+
     pub fn from_bytes(bytes: &[u8]) -> Result<Self, &'static str> {
         if bytes.len() < 54 {
             return Err("File too small");
@@ -50,9 +236,21 @@ impl BmpImage {
         let pixel_data = &bytes[data_offset..];
 
         let bgra_data = match bits_per_pixel {
+            16 => {
+                let masks = if compression == 3 {
+                    (
+                        read_u32_le(bytes, 54)?,
+                        read_u32_le(bytes, 58)?,
+                        read_u32_le(bytes, 62)?,
+                    )
+                } else {
+                    (0x7C00, 0x03E0, 0x001F)
+                };
+                parse_16bit(pixel_data, width, height, top_down, masks)?
+            }
             24 => parse_24bit(pixel_data, width, height, top_down)?,
             32 => parse_32bit(pixel_data, width, height, top_down)?,
-            _ => return Err("Only 24 or 32-bit BMP supported"),
+            _ => return Err("Only 16, 24 or 32-bit BMP supported"),
         };
 
         Ok(BmpImage {
@@ -61,6 +259,75 @@ impl BmpImage {
             data: bgra_data,
         })
     }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        encode_bgra32(self.width, self.height, &self.data)
+    }
+}
+
+pub(crate) fn encode_bgra32(width: u32, height: u32, data: &[u8]) -> Vec<u8> {
+    let row_size = (width * 4) as usize;
+    let image_size = row_size * height as usize;
+    let file_size = 54 + image_size;
+
+    let mut bytes = Vec::with_capacity(file_size);
+    bytes.extend_from_slice(b"BM");
+    write_u32_le(&mut bytes, file_size as u32);
+    write_u32_le(&mut bytes, 0);
+    write_u32_le(&mut bytes, 54);
+
+    write_u32_le(&mut bytes, 40);
+    write_u32_le(&mut bytes, width);
+    write_u32_le(&mut bytes, height);
+    write_u16_le(&mut bytes, 1);
+    write_u16_le(&mut bytes, 32);
+    write_u32_le(&mut bytes, 0);
+    write_u32_le(&mut bytes, image_size as u32);
+    write_u32_le(&mut bytes, 2835);
+    write_u32_le(&mut bytes, 2835);
+    write_u32_le(&mut bytes, 0);
+    write_u32_le(&mut bytes, 0);
+
+    for y in (0..height as usize).rev() {
+        let row_offset = y * row_size;
+        match data.get(row_offset..row_offset + row_size) {
+            Some(row) => bytes.extend_from_slice(row),
+            None => bytes.resize(bytes.len() + row_size, 0),
+        }
+    }
+
+    bytes
+}
+
+fn channel_value(color: Color, channel: usize) -> u8 {
+    match channel {
+        0 => color.r,
+        1 => color.g,
+        _ => color.b,
+    }
+}
+
+fn widest_channel(colors: &[Color]) -> (usize, u8) {
+    (0..3)
+        .map(|channel| {
+            let (min, max) = colors.iter().fold((u8::MAX, u8::MIN), |(min, max), &c| {
+                let value = channel_value(c, channel);
+                (min.min(value), max.max(value))
+            });
+            (channel, max.saturating_sub(min))
+        })
+        .max_by_key(|&(_, range)| range)
+        .unwrap_or((0, 0))
+}
+
+#[inline]
+fn write_u16_le(bytes: &mut Vec<u8>, value: u16) {
+    bytes.extend_from_slice(&value.to_le_bytes());
+}
+
+#[inline]
+pub(crate) fn write_u32_le(bytes: &mut Vec<u8>, value: u32) {
+    bytes.extend_from_slice(&value.to_le_bytes());
 }
 
 #[inline]
@@ -73,7 +340,7 @@ fn read_u16_le(bytes: &[u8], offset: usize) -> Result<u16, &'static str> {
 }
 
 #[inline]
-fn read_u32_le(bytes: &[u8], offset: usize) -> Result<u32, &'static str> {
+pub(crate) fn read_u32_le(bytes: &[u8], offset: usize) -> Result<u32, &'static str> {
     bytes
         .get(offset..offset + 4)
         .and_then(|s| s.try_into().ok())
@@ -90,6 +357,68 @@ fn read_i32_le(bytes: &[u8], offset: usize) -> Result<i32, &'static str> {
         .ok_or("Out of bounds read")
 }
 
+fn expand_channel(pixel: u32, mask: u32) -> u8 {
+    if mask == 0 {
+        return 0;
+    }
+
+    let bits = mask.count_ones();
+    let value = (pixel & mask) >> mask.trailing_zeros();
+    if bits >= 8 {
+        return (value >> (bits - 8)) as u8;
+    }
+
+    let mut expanded = 0u32;
+    let mut filled = 0;
+    while filled < 8 {
+        expanded = (expanded << bits) | value;
+        filled += bits;
+    }
+    (expanded >> (filled - 8)) as u8
+}
+
+fn parse_16bit(
+    pixel_data: &[u8],
+    width: u32,
+    height: u32,
+    top_down: bool,
+    masks: (u32, u32, u32),
+) -> Result<Vec<u8>, &'static str> {
+    let row_size = (width
+        .checked_mul(2)
+        .and_then(|w| w.checked_add(3))
+        .ok_or("Row size overflow")?
+        / 4)
+        * 4;
+
+    let required_size = row_size.checked_mul(height).ok_or("Data size overflow")? as usize;
+
+    if pixel_data.len() < required_size {
+        return Err("Insufficient pixel data");
+    }
+
+    let (r_mask, g_mask, b_mask) = masks;
+    let mut bgra_data = Vec::with_capacity((width * height * 4) as usize);
+
+    for y in 0..height {
+        let actual_y = if top_down { y } else { height - 1 - y };
+        let row_offset = (actual_y * row_size) as usize;
+
+        for x in 0..width {
+            let pixel_offset = row_offset + (x * 2) as usize;
+            let pixel =
+                u16::from_le_bytes([pixel_data[pixel_offset], pixel_data[pixel_offset + 1]]) as u32;
+
+            bgra_data.push(expand_channel(pixel, b_mask));
+            bgra_data.push(expand_channel(pixel, g_mask));
+            bgra_data.push(expand_channel(pixel, r_mask));
+            bgra_data.push(255);
+        }
+    }
+
+    Ok(bgra_data)
+}
+
 fn parse_24bit(
     pixel_data: &[u8],
     width: u32,
@@ -160,3 +489,124 @@ fn parse_32bit(
 
     Ok(bgra_data)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bmp16(width: u32, height: i32, masks: Option<[u32; 3]>, pixels: &[u16]) -> Vec<u8> {
+        let data_offset = if masks.is_some() { 66 } else { 54 };
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(b"BM");
+        write_u32_le(&mut bytes, 0);
+        write_u32_le(&mut bytes, 0);
+        write_u32_le(&mut bytes, data_offset);
+        write_u32_le(&mut bytes, 40);
+        write_u32_le(&mut bytes, width);
+        write_u32_le(&mut bytes, height as u32);
+        write_u16_le(&mut bytes, 1);
+        write_u16_le(&mut bytes, 16);
+        write_u32_le(&mut bytes, if masks.is_some() { 3 } else { 0 });
+        bytes.resize(54, 0);
+        for mask in masks.into_iter().flatten() {
+            write_u32_le(&mut bytes, mask);
+        }
+        for row in pixels.chunks(width as usize) {
+            let row_end = bytes.len() + (width as usize * 2).next_multiple_of(4);
+            for &pixel in row {
+                write_u16_le(&mut bytes, pixel);
+            }
+            bytes.resize(row_end, 0);
+        }
+        bytes
+    }
+
+    #[test]
+    fn decodes_rgb565() {
+        let masks = Some([0xF800, 0x07E0, 0x001F]);
+        let img =
+            BmpImage::from_bytes(&bmp16(2, -2, masks, &[0xF800, 0x07E0, 0x001F, 0x8410])).unwrap();
+        assert_eq!(img.get_pixel(0, 0), Some(Color::RED));
+        assert_eq!(img.get_pixel(1, 0), Some(Color::GREEN));
+        assert_eq!(img.get_pixel(0, 1), Some(Color::BLUE));
+        assert_eq!(img.get_pixel(1, 1), Some(Color::rgb(132, 130, 132)));
+    }
+
+    #[test]
+    fn decodes_bottom_up_rgb555() {
+        let img = BmpImage::from_bytes(&bmp16(1, 2, None, &[0x7C00, 0x03FF])).unwrap();
+        assert_eq!(img.get_pixel(0, 0), Some(Color::rgb(0, 255, 255)));
+        assert_eq!(img.get_pixel(0, 1), Some(Color::RED));
+    }
+
+    #[test]
+    fn to_bytes_round_trips() {
+        let mut img = BmpImage::new(3, 2);
+        img.set_pixel(0, 0, Color::RED);
+        img.set_pixel(2, 0, Color::rgba(1, 2, 3, 4));
+        img.set_pixel(1, 1, Color::BLUE);
+
+        let decoded = BmpImage::from_bytes(&img.to_bytes()).unwrap();
+        assert_eq!((decoded.width, decoded.height), (3, 2));
+        assert_eq!(decoded.data, img.data);
+    }
+
+    #[test]
+    fn downscale_averages_checkerboard() {
+        let mut img = BmpImage::new(4, 4);
+        for y in 0..4 {
+            for x in 0..4 {
+                let color = if (x + y) % 2 == 0 {
+                    Color::WHITE
+                } else {
+                    Color::BLACK
+                };
+                img.set_pixel(x, y, color);
+            }
+        }
+
+        let small = img.downscale_averaged(2, 2).unwrap();
+        for y in 0..2 {
+            for x in 0..2 {
+                assert_eq!(small.get_pixel(x, y), Some(Color::rgb(127, 127, 127)));
+            }
+        }
+        assert!(img.downscale_averaged(8, 2).is_err());
+        assert!(img.downscale_averaged(0, 2).is_err());
+    }
+
+    #[test]
+    fn palette_finds_distinct_colors() {
+        let colors = [Color::RED, Color::GREEN, Color::BLUE];
+        let mut img = BmpImage::new(4, 4);
+        for y in 0..4 {
+            for x in 0..4 {
+                img.set_pixel(x, y, colors[((x + y) % 3) as usize]);
+            }
+        }
+
+        let mut palette = img.palette(3);
+        palette.sort_by_key(|c| c.to_u32());
+        assert_eq!(palette, [Color::BLUE, Color::GREEN, Color::RED]);
+        assert_eq!(img.palette(8).len(), 3);
+        assert_eq!(img.palette(2).len(), 2);
+    }
+
+    #[test]
+    fn grayscale_and_tint() {
+        let mut img = BmpImage::new(2, 1);
+        img.set_pixel(0, 0, Color::rgba(200, 100, 50, 128));
+        img.set_pixel(1, 0, Color::rgb(10, 220, 90));
+
+        let gray = img.to_grayscale();
+        for x in 0..2 {
+            let c = gray.get_pixel(x, 0).unwrap();
+            assert!(c.r == c.g && c.g == c.b);
+            assert_eq!(c.a, img.get_pixel(x, 0).unwrap().a);
+        }
+
+        let red = img.tint(Color::RED);
+        assert_eq!(red.get_pixel(0, 0), Some(Color::rgba(200, 0, 0, 128)));
+        assert_eq!(red.get_pixel(1, 0), Some(Color::rgb(10, 0, 0)));
+    }
+}
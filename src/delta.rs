@@ -0,0 +1,140 @@
+use crate::bmp::{read_u32_le, write_u32_le, BmpImage};
+use crate::{Color, Sight};
+use alloc::vec::Vec;
+
+impl Sight {
+    pub fn encode_delta(&self, previous: &[u32]) -> Vec<u8> {
+        let current = self.buffer();
+        let changed = |i: usize| previous.get(i) != Some(&current[i]);
+
+        let mut delta = Vec::new();
+        let mut i = 0;
+        while i < current.len() {
+            if !changed(i) {
+                i += 1;
+                continue;
+            }
+
+            let start = i;
+            while i < current.len() && changed(i) {
+                i += 1;
+            }
+
+            write_u32_le(&mut delta, start as u32);
+            write_u32_le(&mut delta, (i - start) as u32);
+            for &pixel in &current[start..i] {
+                write_u32_le(
+                    &mut delta,
+                    Color::from_u32(pixel).to_format(self.pixel_format()),
+                );
+            }
+        }
+
+        delta
+    }
+
+    pub fn apply_delta(&mut self, delta: &[u8]) -> Result<(), &'static str> {
+        let mut cursor = 0;
+        while cursor < delta.len() {
+            let offset = read_u32_le(delta, cursor)? as usize;
+            let len = read_u32_le(delta, cursor + 4)? as usize;
+            cursor += 8;
+
+            let end = offset
+                .checked_add(len)
+                .filter(|&end| end <= self.buffer().len())
+                .ok_or("Delta run outside framebuffer")?;
+            let run = delta
+                .get(cursor..cursor + len * 4)
+                .ok_or("Truncated delta run")?;
+
+            let format = self.pixel_format();
+            let pixels = self.buffer_mut();
+            for (pixel, bytes) in pixels[offset..end].iter_mut().zip(run.chunks_exact(4)) {
+                let value = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+                *pixel = Color::from_format(value, format).to_u32();
+            }
+            cursor += len * 4;
+        }
+
+        Ok(())
+    }
+
+    pub fn diff(&self, other: &[u32]) -> usize {
+        self.buffer()
+            .iter()
+            .enumerate()
+            .filter(|&(i, pixel)| other.get(i) != Some(pixel))
+            .count()
+    }
+
+    pub fn diff_image(&self, other: &[u32], match_color: Color, diff_color: Color) -> BmpImage {
+        let mut image = BmpImage::new(self.width(), self.height());
+        let width = self.width() as usize;
+
+        for (i, pixel) in self.buffer().iter().enumerate() {
+            let color = if other.get(i) == Some(pixel) {
+                match_color
+            } else {
+                diff_color
+            };
+            image.set_pixel((i % width) as u32, (i / width) as u32, color);
+        }
+
+        image
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{PixelFormat, Rect};
+
+    #[test]
+    fn delta_round_trips() {
+        let mut sight = Sight::headless(8, 4);
+        sight.clear(Color::BLACK);
+        let previous = sight.buffer().to_vec();
+        sight.fill_rect(Rect::new(1, 1, 3, 2), Color::RED);
+        sight.put_pixel(7, 3, Color::BLUE);
+        let current = sight.buffer().to_vec();
+
+        let delta = sight.encode_delta(&previous);
+        assert_eq!(delta.len(), 3 * 8 + 7 * 4);
+        assert!(sight.encode_delta(&current).is_empty());
+
+        sight.buffer_mut().copy_from_slice(&previous);
+        sight.apply_delta(&delta).unwrap();
+        assert_eq!(sight.buffer(), &current[..]);
+        assert!(sight.apply_delta(&delta[..10]).is_err());
+    }
+
+    #[test]
+    fn delta_payload_uses_pixel_format() {
+        let color = Color::rgba(0x11, 0x22, 0x33, 0x44);
+        let mut sight = Sight::headless(2, 2);
+        sight.set_pixel_format(PixelFormat::Rgba);
+        sight.put_pixel(0, 0, color);
+        let delta = sight.encode_delta(&[0; 4]);
+        assert_eq!(read_u32_le(&delta, 8), Ok(0x1122_3344));
+
+        let mut target = Sight::headless(2, 2);
+        target.set_pixel_format(PixelFormat::Rgba);
+        target.apply_delta(&delta).unwrap();
+        assert_eq!(target.get_pixel(0, 0), Some(color));
+    }
+
+    #[test]
+    fn diff_counts_changed_pixels() {
+        let mut sight = Sight::headless(4, 3);
+        let previous = sight.buffer().to_vec();
+        assert_eq!(sight.diff(&previous), 0);
+
+        sight.put_pixel(2, 1, Color::RED);
+        assert_eq!(sight.diff(&previous), 1);
+        let image = sight.diff_image(&previous, Color::BLACK, Color::WHITE);
+        assert_eq!(image.get_pixel(2, 1), Some(Color::WHITE));
+        assert_eq!(image.get_pixel(1, 2), Some(Color::BLACK));
+        assert_eq!(sight.diff(&previous[..6]), 6);
+    }
+}
@@ -1,13 +1,21 @@
 #![no_std]
-use libm::{cosf, sinf, sqrtf};
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+use libm::{atan2f, cosf, fmodf, powf, sinf, sqrtf};
 extern crate alloc;
+#[cfg(test)]
+extern crate std;
 pub mod bdf;
 pub mod bmp;
+mod delta;
+mod plot;
+mod widgets;
 
 const SYS_GPU_INFO: u64 = 12;
 const SYS_GPU_MAP: u64 = 13;
 const SYS_GPU_FLUSH: u64 = 14;
 
+#[cfg(all(not(test), target_os = "none"))]
 #[inline(always)]
 fn syscall3(num: u64, a0: u64, a1: u64, a2: u64) -> u64 {
     let ret: u64;
@@ -25,6 +33,20 @@ fn syscall3(num: u64, a0: u64, a1: u64, a2: u64) -> u64 {
     ret
 }
 
+// Host builds (benchmarks) have no sOS kernel behind `int 0x80`.
+#[cfg(all(not(test), not(target_os = "none")))]
+fn syscall3(_num: u64, _a0: u64, _a1: u64, _a2: u64) -> u64 {
+    0
+}
+
+#[cfg(test)]
+fn syscall3(num: u64, _a0: u64, _a1: u64, _a2: u64) -> u64 {
+    if num == SYS_GPU_FLUSH {
+        tests::FLUSHES.with(|flushes| flushes.set(flushes.get() + 1));
+    }
+    0
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct Color {
     pub b: u8,
@@ -43,7 +65,40 @@ impl Color {
     }
 
     pub const fn to_u32(self) -> u32 {
-        (self.b as u32) | ((self.g as u32) << 8) | ((self.r as u32) << 16) | ((self.a as u32) << 24)
+        self.to_format(PixelFormat::Argb)
+    }
+
+    pub const fn from_u32(value: u32) -> Self {
+        Self::from_format(value, PixelFormat::Argb)
+    }
+
+    pub const fn to_format(self, format: PixelFormat) -> u32 {
+        let (r, g, b, a) = (self.r as u32, self.g as u32, self.b as u32, self.a as u32);
+        match format {
+            PixelFormat::Argb => (a << 24) | (r << 16) | (g << 8) | b,
+            PixelFormat::Rgba => (r << 24) | (g << 16) | (b << 8) | a,
+            PixelFormat::Abgr => (a << 24) | (b << 16) | (g << 8) | r,
+            PixelFormat::Bgra => (b << 24) | (g << 16) | (r << 8) | a,
+        }
+    }
+
+    pub const fn from_format(value: u32, format: PixelFormat) -> Self {
+        let [hi, upper, lower, lo] = value.to_be_bytes();
+        match format {
+            PixelFormat::Argb => Self::rgba(upper, lower, lo, hi),
+            PixelFormat::Rgba => Self::rgba(hi, upper, lower, lo),
+            PixelFormat::Abgr => Self::rgba(lo, lower, upper, hi),
+            PixelFormat::Bgra => Self::rgba(lower, upper, hi, lo),
+        }
+    }
+
+    pub const fn with_alpha(self, a: u8) -> Color {
+        Color { a, ..self }
+    }
+
+    pub fn scale_alpha(self, factor: f32) -> Color {
+        let factor = factor.clamp(0.0, 1.0);
+        self.with_alpha((self.a as f32 * factor) as u8)
     }
 
     pub fn blend(self, background: Color) -> Color {
@@ -56,12 +111,18 @@ impl Color {
 
         let alpha = self.a as u32;
         let inv_alpha = 255 - alpha;
+        let background_weight = background.a as u32 * inv_alpha / 255;
+        let out_alpha = alpha + background_weight;
+
+        let mix = |fg: u8, bg: u8| {
+            ((fg as u32 * alpha + bg as u32 * background_weight) / out_alpha) as u8
+        };
 
         Color {
-            r: ((self.r as u32 * alpha + background.r as u32 * inv_alpha) / 255) as u8,
-            g: ((self.g as u32 * alpha + background.g as u32 * inv_alpha) / 255) as u8,
-            b: ((self.b as u32 * alpha + background.b as u32 * inv_alpha) / 255) as u8,
-            a: 255,
+            r: mix(self.r, background.r),
+            g: mix(self.g, background.g),
+            b: mix(self.b, background.b),
+            a: out_alpha as u8,
         }
     }
 
@@ -75,6 +136,87 @@ impl Color {
         }
     }
 
+    pub fn distance(self, other: Color) -> f32 {
+        let dr = self.r as f32 - other.r as f32;
+        let dg = self.g as f32 - other.g as f32;
+        let db = self.b as f32 - other.b as f32;
+        let da = self.a as f32 - other.a as f32;
+        sqrtf(dr * dr + dg * dg + db * db + da * da)
+    }
+
+    pub fn luminance(self) -> u8 {
+        ((self.r as u32 * 299 + self.g as u32 * 587 + self.b as u32 * 114 + 500) / 1000) as u8
+    }
+
+    pub fn relative_luminance(self) -> f32 {
+        0.2126 * linear_channel(self.r)
+            + 0.7152 * linear_channel(self.g)
+            + 0.0722 * linear_channel(self.b)
+    }
+
+    pub fn contrast_ratio(self, other: Color) -> f32 {
+        let l1 = self.relative_luminance();
+        let l2 = other.relative_luminance();
+        (l1.max(l2) + 0.05) / (l1.min(l2) + 0.05)
+    }
+
+    pub fn best_text_color(self) -> Color {
+        if self.contrast_ratio(Color::WHITE) >= self.contrast_ratio(Color::BLACK) {
+            Color::WHITE
+        } else {
+            Color::BLACK
+        }
+    }
+
+    pub fn mix(colors: &[(Color, f32)]) -> Color {
+        let mut total = 0.0;
+        let (mut r, mut g, mut b, mut a) = (0.0, 0.0, 0.0, 0.0);
+
+        for &(color, weight) in colors {
+            if weight <= 0.0 {
+                continue;
+            }
+            total += weight;
+            r += color.r as f32 * weight;
+            g += color.g as f32 * weight;
+            b += color.b as f32 * weight;
+            a += color.a as f32 * weight;
+        }
+
+        if total <= 0.0 {
+            return Color::TRANSPARENT;
+        }
+
+        Color {
+            r: (r / total).round() as u8,
+            g: (g / total).round() as u8,
+            b: (b / total).round() as u8,
+            a: (a / total).round() as u8,
+        }
+    }
+
+    pub fn average(colors: &[Color]) -> Color {
+        if colors.is_empty() {
+            return Color::TRANSPARENT;
+        }
+
+        let count = colors.len() as u64;
+        let (mut r, mut g, mut b, mut a) = (0u64, 0u64, 0u64, 0u64);
+        for color in colors {
+            r += color.r as u64;
+            g += color.g as u64;
+            b += color.b as u64;
+            a += color.a as u64;
+        }
+
+        Color {
+            r: ((r + count / 2) / count) as u8,
+            g: ((g + count / 2) / count) as u8,
+            b: ((b + count / 2) / count) as u8,
+            a: ((a + count / 2) / count) as u8,
+        }
+    }
+
     pub const BLACK: Color = Color::rgb(0, 0, 0);
     pub const WHITE: Color = Color::rgb(255, 255, 255);
     pub const RED: Color = Color::rgb(255, 0, 0);
@@ -91,6 +233,54 @@ impl Color {
     pub const TRANSPARENT: Color = Color::rgba(0, 0, 0, 0);
 }
 
+#[derive(Debug, Clone, PartialEq)]
+pub struct Gradient {
+    stops: Vec<(f32, Color)>,
+}
+
+impl Gradient {
+    pub fn new(stops: &[(f32, Color)]) -> Self {
+        let mut stops: Vec<_> = stops
+            .iter()
+            .map(|&(position, color)| (position.clamp(0.0, 1.0), color))
+            .collect();
+        stops.sort_by(|a, b| a.0.total_cmp(&b.0));
+        Self { stops }
+    }
+
+    pub fn stops(&self) -> &[(f32, Color)] {
+        &self.stops
+    }
+
+    pub fn sample(&self, t: f32) -> Color {
+        let Some(&(first_pos, first)) = self.stops.first() else {
+            return Color::TRANSPARENT;
+        };
+        if t <= first_pos {
+            return first;
+        }
+
+        for pair in self.stops.windows(2) {
+            let (start, c1) = pair[0];
+            let (end, c2) = pair[1];
+            if t <= end {
+                if end <= start {
+                    return c2;
+                }
+                return c1.lerp(c2, (t - start) / (end - start));
+            }
+        }
+
+        self.stops[self.stops.len() - 1].1
+    }
+}
+
+pub trait Drawable {
+    fn width(&self) -> u32;
+    fn height(&self) -> u32;
+    fn pixel(&self, x: u32, y: u32) -> Color;
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct Point {
     pub x: i32,
@@ -107,6 +297,21 @@ impl Point {
         let dy = (other.y - self.y) as f32;
         sqrtf(dx * dx + dy * dy)
     }
+
+    pub fn rotate(self, angle: f32) -> Point {
+        self.rotate_around(Point::new(0, 0), angle)
+    }
+
+    pub fn rotate_around(self, center: Point, angle: f32) -> Point {
+        let (sin, cos) = (sinf(angle), cosf(angle));
+        let dx = (self.x - center.x) as f32;
+        let dy = (self.y - center.y) as f32;
+
+        Point::new(
+            center.x + (dx * cos - dy * sin).round() as i32,
+            center.y + (dx * sin + dy * cos).round() as i32,
+        )
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -127,6 +332,24 @@ impl Rect {
         }
     }
 
+    pub fn from_corners(a: Point, b: Point) -> Self {
+        Self::new(
+            a.x.min(b.x),
+            a.y.min(b.y),
+            a.x.abs_diff(b.x),
+            a.y.abs_diff(b.y),
+        )
+    }
+
+    pub fn from_center(center: Point, width: u32, height: u32) -> Self {
+        Self::new(
+            center.x - (width / 2) as i32,
+            center.y - (height / 2) as i32,
+            width,
+            height,
+        )
+    }
+
     pub fn contains(&self, point: Point) -> bool {
         point.x >= self.x
             && point.x < self.x + self.width as i32
@@ -140,6 +363,42 @@ impl Rect {
             && self.y < other.y + other.height as i32
             && self.y + self.height as i32 > other.y
     }
+
+    pub fn points(&self) -> impl Iterator<Item = Point> {
+        let Rect {
+            x,
+            y,
+            width,
+            height,
+        } = *self;
+        (0..height as i32)
+            .flat_map(move |dy| (0..width as i32).map(move |dx| Point::new(x + dx, y + dy)))
+    }
+}
+
+const FRAME_HISTORY: usize = 60;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FrameStats {
+    pub last_frame_ms: f32,
+    pub average_fps: f32,
+    pub pixels_written: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PixelFormat {
+    #[default]
+    Argb,
+    Rgba,
+    Abgr,
+    Bgra,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StencilMode {
+    None,
+    Inside,
+    Outside,
 }
 
 pub struct Sight {
@@ -147,6 +406,16 @@ pub struct Sight {
     width: u32,
     height: u32,
     dirty: bool,
+    stencil: Vec<bool>,
+    stencil_mode: StencilMode,
+    pixel_format: PixelFormat,
+    opacity: f32,
+    y_up: bool,
+    antialias: bool,
+    frame_times: [f32; FRAME_HISTORY],
+    frame_count: usize,
+    pixels_written: u32,
+    last_pixels_written: u32,
 }
 
 impl Sight {
@@ -178,9 +447,41 @@ impl Sight {
             width,
             height,
             dirty: false,
+            stencil: Vec::new(),
+            stencil_mode: StencilMode::None,
+            pixel_format: PixelFormat::default(),
+            opacity: 1.0,
+            y_up: false,
+            antialias: true,
+            frame_times: [0.0; FRAME_HISTORY],
+            frame_count: 0,
+            pixels_written: 0,
+            last_pixels_written: 0,
         })
     }
 
+    /// Off-screen canvas backed by a leaked heap buffer, for host tests and benchmarks.
+    #[cfg(not(target_os = "none"))]
+    pub fn headless(width: u32, height: u32) -> Sight {
+        let pixels = alloc::vec![0u32; (width * height) as usize].leak();
+        Sight {
+            fb: pixels.as_mut_ptr(),
+            width,
+            height,
+            dirty: false,
+            stencil: Vec::new(),
+            stencil_mode: StencilMode::None,
+            pixel_format: PixelFormat::default(),
+            opacity: 1.0,
+            y_up: false,
+            antialias: true,
+            frame_times: [0.0; FRAME_HISTORY],
+            frame_count: 0,
+            pixels_written: 0,
+            last_pixels_written: 0,
+        }
+    }
+
     pub fn width(&self) -> u32 {
         self.width
     }
@@ -191,15 +492,70 @@ impl Sight {
 
     pub fn clear(&mut self, color: Color) {
         let pixel = color.to_u32();
-        unsafe {
-            for y in 0..self.height {
-                for x in 0..self.width {
-                    let offset = (y * self.width + x) as usize;
-                    *self.fb.add(offset) = pixel;
-                }
-            }
+        self.pixels_mut().fill(pixel);
+        self.mark_dirty(self.width * self.height);
+    }
+
+    pub fn clear_transparent(&mut self) {
+        self.clear(Color::TRANSPARENT);
+    }
+
+    pub fn clear_checker(&mut self, cell: u32, c1: Color, c2: Color) {
+        let cell = cell.max(1);
+        let width = self.width;
+        let (p1, p2) = (c1.to_u32(), c2.to_u32());
+
+        for (i, pixel) in self.pixels_mut().iter_mut().enumerate() {
+            let x = i as u32 % width;
+            let y = i as u32 / width;
+            *pixel = if (x / cell + y / cell).is_multiple_of(2) {
+                p1
+            } else {
+                p2
+            };
         }
-        self.dirty = true;
+        self.mark_dirty(self.width * self.height);
+    }
+
+    pub fn get_pixel(&self, x: i32, y: i32) -> Option<Color> {
+        if x < 0 || y < 0 || x >= self.width as i32 || y >= self.height as i32 {
+            return None;
+        }
+
+        let offset = (y as u32 * self.width + x as u32) as usize;
+        unsafe { Some(Color::from_u32(*self.fb.add(offset))) }
+    }
+
+    /// Sets the channel order used when pixels leave the crate through `export_buffer`,
+    /// `encode_delta` and `apply_delta`. The scanout framebuffer itself is always ARGB.
+    pub fn set_pixel_format(&mut self, format: PixelFormat) {
+        self.pixel_format = format;
+    }
+
+    pub fn pixel_format(&self) -> PixelFormat {
+        self.pixel_format
+    }
+
+    pub fn export_buffer(&self) -> Vec<u32> {
+        self.buffer()
+            .iter()
+            .map(|&pixel| Color::from_u32(pixel).to_format(self.pixel_format))
+            .collect()
+    }
+
+    pub fn buffer(&self) -> &[u32] {
+        let len = (self.width * self.height) as usize;
+        unsafe { core::slice::from_raw_parts(self.fb, len) }
+    }
+
+    pub fn buffer_mut(&mut self) -> &mut [u32] {
+        self.mark_dirty(self.width * self.height);
+        self.pixels_mut()
+    }
+
+    fn pixels_mut(&mut self) -> &mut [u32] {
+        let len = (self.width * self.height) as usize;
+        unsafe { core::slice::from_raw_parts_mut(self.fb, len) }
     }
 
     pub fn put_pixel(&mut self, x: i32, y: i32, color: Color) {
@@ -207,6 +563,14 @@ impl Sight {
             syscall3(2, 1, b"put_pixel: out of bounds\n".as_ptr() as u64, 25);
             return;
         }
+        if self.opacity < 1.0 {
+            self.blend_pixel(x, y, color);
+            return;
+        }
+        if !self.stencil_passes(x, y) {
+            return;
+        }
+        let y = self.native_y(y);
 
         let pixel = color.to_u32();
         unsafe {
@@ -221,104 +585,190 @@ impl Sight {
                 syscall3(2, 1, b"Pixel written OK\n".as_ptr() as u64, 17);
             }
         }
-        self.dirty = true;
+        self.mark_dirty(1);
     }
 
-    fn put_pixel_aa(&mut self, x: i32, y: i32, color: Color, alpha: f32) {
+    pub fn blend_pixel(&mut self, x: i32, y: i32, color: Color) {
         if x < 0 || y < 0 || x >= self.width as i32 || y >= self.height as i32 {
             return;
         }
+        if !self.stencil_passes(x, y) {
+            return;
+        }
+        let y = self.native_y(y);
 
         unsafe {
             let offset = (y as u32 * self.width + x as u32) as usize;
+            let existing = Color::from_u32(*self.fb.add(offset));
+            let color = color.scale_alpha(self.opacity);
+            *self.fb.add(offset) = color.blend(existing).to_u32();
+        }
+        self.mark_dirty(1);
+    }
 
-            let existing_u32 = *self.fb.add(offset);
-            let existing = Color {
-                b: (existing_u32 & 0xFF) as u8,
-                g: ((existing_u32 >> 8) & 0xFF) as u8,
-                r: ((existing_u32 >> 16) & 0xFF) as u8,
-                a: ((existing_u32 >> 24) & 0xFF) as u8,
-            };
+    fn put_pixel_aa(&mut self, x: i32, y: i32, color: Color, alpha: f32) {
+        if !self.antialias {
+            if alpha >= 0.5 {
+                self.blend_pixel(x, y, color);
+            }
+            return;
+        }
 
-            let blended_color = Color::rgba(
-                color.r,
-                color.g,
-                color.b,
-                (color.a as f32 * alpha.clamp(0.0, 1.0)) as u8,
-            );
+        self.blend_pixel(x, y, color.scale_alpha(alpha));
+    }
 
-            let final_color = blended_color.blend(existing);
-            *self.fb.add(offset) = final_color.to_u32();
-        }
-        self.dirty = true;
+    pub fn with_opacity<F: FnOnce(&mut Sight)>(&mut self, opacity: f32, f: F) {
+        let previous = self.opacity;
+        self.opacity = previous * opacity.clamp(0.0, 1.0);
+        f(self);
+        self.opacity = previous;
     }
 
-    pub fn draw_line(&mut self, p1: Point, p2: Point, color: Color) {
-        let mut x0 = p1.x as f32;
-        let mut y0 = p1.y as f32;
-        let mut x1 = p2.x as f32;
-        let mut y1 = p2.y as f32;
+    pub fn set_antialias(&mut self, on: bool) {
+        self.antialias = on;
+    }
+
+    pub fn antialias(&self) -> bool {
+        self.antialias
+    }
 
-        let steep = (y1 - y0).abs() > (x1 - x0).abs();
+    pub fn set_stencil_mode(&mut self, mode: StencilMode) {
+        self.stencil_mode = mode;
+    }
 
-        if steep {
-            core::mem::swap(&mut x0, &mut y0);
-            core::mem::swap(&mut x1, &mut y1);
+    pub fn stencil_mode(&self) -> StencilMode {
+        self.stencil_mode
+    }
+
+    pub fn clear_stencil(&mut self) {
+        self.stencil.clear();
+    }
+
+    pub fn fill_stencil_rect(&mut self, rect: Rect, value: bool) {
+        self.ensure_stencil();
+
+        let x1 = rect.x.max(0);
+        let y1 = rect.y.max(0);
+        let x2 = (rect.x + rect.width as i32).min(self.width as i32);
+        let y2 = (rect.y + rect.height as i32).min(self.height as i32);
+
+        for y in y1..y2 {
+            for x in x1..x2 {
+                self.stencil[(y as u32 * self.width + x as u32) as usize] = value;
+            }
         }
+    }
+
+    pub fn set_stencil_from<F>(&mut self, mut inside: F)
+    where
+        F: FnMut(i32, i32) -> bool,
+    {
+        self.ensure_stencil();
 
-        if x0 > x1 {
-            core::mem::swap(&mut x0, &mut x1);
-            core::mem::swap(&mut y0, &mut y1);
+        for y in 0..self.height {
+            for x in 0..self.width {
+                self.stencil[(y * self.width + x) as usize] = inside(x as i32, y as i32);
+            }
         }
+    }
 
-        let dx = x1 - x0;
-        let dy = y1 - y0;
-        let gradient = if dx == 0.0 { 1.0 } else { dy / dx };
+    fn ensure_stencil(&mut self) {
+        let len = (self.width * self.height) as usize;
+        if self.stencil.len() != len {
+            self.stencil.resize(len, false);
+        }
+    }
 
-        let xend = x0.round();
-        let yend = y0 + gradient * (xend - x0);
-        let xgap = 1.0 - (x0 + 0.5).fract();
-        let xpxl1 = xend as i32;
-        let ypxl1 = yend.floor() as i32;
+    fn stencil_passes(&self, x: i32, y: i32) -> bool {
+        if self.stencil_mode == StencilMode::None {
+            return true;
+        }
 
-        if steep {
-            self.put_pixel_aa(ypxl1, xpxl1, color, (1.0 - yend.fract()) * xgap);
-            self.put_pixel_aa(ypxl1 + 1, xpxl1, color, yend.fract() * xgap);
-        } else {
-            self.put_pixel_aa(xpxl1, ypxl1, color, (1.0 - yend.fract()) * xgap);
-            self.put_pixel_aa(xpxl1, ypxl1 + 1, color, yend.fract() * xgap);
+        let offset = (y as u32 * self.width + x as u32) as usize;
+        let inside = self.stencil.get(offset).copied().unwrap_or(false);
+        match self.stencil_mode {
+            StencilMode::Inside => inside,
+            StencilMode::Outside => !inside,
+            StencilMode::None => true,
         }
+    }
 
-        let mut intery = yend + gradient;
+    /// Flips the y-axis so `y = 0` is the bottom row for all drawing calls.
+    /// `get_pixel` and raw access through `fb` stay in native top-down order.
+    pub fn set_y_up(&mut self, on: bool) {
+        self.y_up = on;
+    }
 
-        let xend = x1.round();
-        let yend = y1 + gradient * (xend - x1);
-        let xgap = (x1 + 0.5).fract();
-        let xpxl2 = xend as i32;
-        let ypxl2 = yend.floor() as i32;
+    pub fn y_up(&self) -> bool {
+        self.y_up
+    }
 
-        if steep {
-            self.put_pixel_aa(ypxl2, xpxl2, color, (1.0 - yend.fract()) * xgap);
-            self.put_pixel_aa(ypxl2 + 1, xpxl2, color, yend.fract() * xgap);
+    fn native_y(&self, y: i32) -> i32 {
+        if self.y_up {
+            self.height as i32 - 1 - y
         } else {
-            self.put_pixel_aa(xpxl2, ypxl2, color, (1.0 - yend.fract()) * xgap);
-            self.put_pixel_aa(xpxl2, ypxl2 + 1, color, yend.fract() * xgap);
+            y
         }
+    }
 
-        for x in (xpxl1 + 1)..xpxl2 {
-            let y = intery.floor() as i32;
-            let frac = intery.fract();
+    fn read_pixel(&self, x: i32, y: i32) -> Color {
+        let y = self.native_y(y);
+        let offset = (y as u32 * self.width + x as u32) as usize;
+        unsafe { Color::from_u32(*self.fb.add(offset)) }
+    }
 
-            if steep {
-                self.put_pixel_aa(y, x, color, 1.0 - frac);
-                self.put_pixel_aa(y + 1, x, color, frac);
-            } else {
-                self.put_pixel_aa(x, y, color, 1.0 - frac);
-                self.put_pixel_aa(x, y + 1, color, frac);
-            }
+    pub fn draw_line(&mut self, p1: Point, p2: Point, color: Color) {
+        wu_line(
+            p1.x as f32,
+            p1.y as f32,
+            p2.x as f32,
+            p2.y as f32,
+            |x, y, alpha| self.put_pixel_aa(x, y, color, alpha),
+        );
+    }
 
-            intery += gradient;
+    pub fn draw_line_gradient(&mut self, p1: Point, p2: Point, c1: Color, c2: Color) {
+        let dx = (p2.x - p1.x) as f32;
+        let dy = (p2.y - p1.y) as f32;
+        let length_sq = dx * dx + dy * dy;
+
+        wu_line(
+            p1.x as f32,
+            p1.y as f32,
+            p2.x as f32,
+            p2.y as f32,
+            |x, y, alpha| {
+                let t = if length_sq == 0.0 {
+                    0.0
+                } else {
+                    ((x - p1.x) as f32 * dx + (y - p1.y) as f32 * dy) / length_sq
+                };
+                self.put_pixel_aa(x, y, c1.lerp(c2, t), alpha);
+            },
+        );
+    }
+
+    pub fn draw_line_capped(&mut self, p1: Point, p2: Point, color: Color, round_caps: bool) {
+        if !round_caps {
+            self.draw_line(p1, p2, color);
+            return;
         }
+
+        let mut coverage = BTreeMap::new();
+        wu_line(
+            p1.x as f32,
+            p1.y as f32,
+            p2.x as f32,
+            p2.y as f32,
+            |x, y, alpha| add_coverage(&mut coverage, x, y, alpha),
+        );
+
+        let dx = (p2.x - p1.x) as f32;
+        let dy = (p2.y - p1.y) as f32;
+        add_cap_coverage(&mut coverage, p1, -dx, -dy);
+        add_cap_coverage(&mut coverage, p2, dx, dy);
+
+        self.blend_coverage(&coverage, color);
     }
 
     pub fn draw_thick_line(&mut self, p1: Point, p2: Point, color: Color, thickness: u32) {
@@ -362,11 +812,80 @@ impl Sight {
         self.draw_line(Point::new(rect.x, y2), Point::new(rect.x, rect.y), color);
     }
 
+    pub fn draw_bevel(&mut self, rect: Rect, light: Color, dark: Color, raised: bool) {
+        if rect.width == 0 || rect.height == 0 {
+            return;
+        }
+
+        let (top_left, bottom_right) = if raised { (light, dark) } else { (dark, light) };
+        let right = rect.x + rect.width as i32 - 1;
+        let bottom = rect.y + rect.height as i32 - 1;
+
+        self.draw_hline(rect.x, right, rect.y, top_left);
+        self.draw_vline(rect.x, rect.y, bottom, top_left);
+        self.draw_hline(rect.x, right, bottom, bottom_right);
+        self.draw_vline(right, rect.y, bottom, bottom_right);
+    }
+
+    pub fn draw_marching_ants(&mut self, rect: Rect, phase: u32, c1: Color, c2: Color) {
+        self.draw_dashed_rect(rect, 4, phase, c1, c2);
+    }
+
+    fn draw_dashed_rect(&mut self, rect: Rect, dash: u32, phase: u32, c1: Color, c2: Color) {
+        if rect.width == 0 || rect.height == 0 || dash == 0 {
+            return;
+        }
+
+        let right = rect.x + rect.width as i32 - 1;
+        let bottom = rect.y + rect.height as i32 - 1;
+
+        let top_edge = (rect.x..=right).map(|x| (x, rect.y));
+        let right_edge = (rect.y + 1..=bottom).map(|y| (right, y));
+        let bottom_edge = (rect.x..right)
+            .rev()
+            .filter(|_| bottom > rect.y)
+            .map(|x| (x, bottom));
+        let left_edge = (rect.y + 1..bottom)
+            .rev()
+            .filter(|_| right > rect.x)
+            .map(|y| (rect.x, y));
+
+        let offset = phase % (2 * dash);
+        let perimeter = top_edge
+            .chain(right_edge)
+            .chain(bottom_edge)
+            .chain(left_edge);
+        for (i, (x, y)) in perimeter.enumerate() {
+            let color = if ((i as u32 + offset) / dash).is_multiple_of(2) {
+                c1
+            } else {
+                c2
+            };
+            self.put_pixel_clipped(x, y, color);
+        }
+    }
+
     pub fn fill_rect(&mut self, rect: Rect, color: Color) {
-        let x1 = rect.x.max(0);
-        let y1 = rect.y.max(0);
-        let x2 = (rect.x + rect.width as i32).min(self.width as i32);
-        let y2 = (rect.y + rect.height as i32).min(self.height as i32);
+        let Some((x1, y1, x2, y2)) = self.clip_rect(rect) else {
+            return;
+        };
+
+        if color.a == 255 && self.opacity >= 1.0 && self.stencil_mode == StencilMode::None {
+            let pixel = color.to_u32();
+            let width = self.width as usize;
+            let (y1, y2) = if self.y_up {
+                (self.height as i32 - y2, self.height as i32 - y1)
+            } else {
+                (y1, y2)
+            };
+            let pixels = self.pixels_mut();
+            for y in y1..y2 {
+                let row = y as usize * width;
+                pixels[row + x1 as usize..row + x2 as usize].fill(pixel);
+            }
+            self.mark_dirty(((x2 - x1) * (y2 - y1)) as u32);
+            return;
+        }
 
         for y in y1..y2 {
             for x in x1..x2 {
@@ -375,6 +894,24 @@ impl Sight {
         }
     }
 
+    pub fn fill_rects(&mut self, rects: &[(Rect, Color)]) {
+        for &(rect, color) in rects {
+            self.fill_rect(rect, color);
+        }
+    }
+
+    fn clip_rect(&self, rect: Rect) -> Option<(i32, i32, i32, i32)> {
+        let x1 = rect.x.max(0);
+        let y1 = rect.y.max(0);
+        let x2 = (rect.x + rect.width as i32).min(self.width as i32);
+        let y2 = (rect.y + rect.height as i32).min(self.height as i32);
+
+        if x1 >= x2 || y1 >= y2 {
+            return None;
+        }
+        Some((x1, y1, x2, y2))
+    }
+
     pub fn draw_circle(&mut self, center: Point, radius: i32, color: Color) {
         let mut x = radius;
         let mut y = 0;
@@ -416,12 +953,29 @@ impl Sight {
         let min_y = (center.y - radius - 1).max(0);
         let max_y = (center.y + radius + 1).min(self.height as i32 - 1);
 
+        let r_inner_sq = if radius >= 1 {
+            ((radius - 1) * (radius - 1)) as u32
+        } else {
+            0
+        };
+
         for y in min_y..=max_y {
+            let dy = y - center.y;
+            let dy_sq = (dy * dy) as u32;
+            let inner_half = if radius >= 1 && dy_sq <= r_inner_sq {
+                Some(isqrt(r_inner_sq - dy_sq) as i32)
+            } else {
+                None
+            };
+
             for x in min_x..=max_x {
-                let dx = (x - center.x) as f32;
-                let dy = (y - center.y) as f32;
-                let dist = sqrtf(dx * dx + dy * dy);
+                let dx = x - center.x;
+                if inner_half.is_some_and(|half| dx.abs() <= half) {
+                    self.put_pixel(x, y, color);
+                    continue;
+                }
 
+                let dist = sqrtf((dx * dx) as f32 + (dy * dy) as f32);
                 if dist <= r_inner {
                     self.put_pixel(x, y, color);
                 } else if dist < r_outer {
@@ -432,13 +986,217 @@ impl Sight {
         }
     }
 
-    pub fn draw_triangle(&mut self, p1: Point, p2: Point, p3: Point, color: Color) {
-        self.draw_line(p1, p2, color);
-        self.draw_line(p2, p3, color);
-        self.draw_line(p3, p1, color);
+    pub fn draw_crosshair(&mut self, center: Point, size: i32, color: Color) {
+        if size < 0 {
+            return;
+        }
+
+        self.draw_hline(center.x - size, center.x + size, center.y, color);
+        self.draw_vline(center.x, center.y - size, center.y - 1, color);
+        self.draw_vline(center.x, center.y + 1, center.y + size, color);
     }
 
-    pub fn fill_triangle(&mut self, p1: Point, p2: Point, p3: Point, color: Color) {
+    pub fn draw_x_marker(&mut self, center: Point, size: i32, color: Color) {
+        if size < 0 {
+            return;
+        }
+
+        for i in -size..=size {
+            self.put_pixel_clipped(center.x + i, center.y + i, color);
+            if i != 0 {
+                self.put_pixel_clipped(center.x + i, center.y - i, color);
+            }
+        }
+    }
+
+    pub fn draw_caret(&mut self, x: i32, top: i32, height: i32, color: Color) {
+        if height <= 0 {
+            return;
+        }
+
+        self.draw_vline(x, top, top + height - 1, color);
+    }
+
+    pub fn draw_point(&mut self, p: Point, size: i32, color: Color) {
+        if size <= 1 {
+            self.put_pixel_clipped(p.x, p.y, color);
+            return;
+        }
+
+        self.fill_rect(
+            Rect::new(p.x - size / 2, p.y - size / 2, size as u32, size as u32),
+            color,
+        );
+    }
+
+    fn draw_hline(&mut self, x1: i32, x2: i32, y: i32, color: Color) {
+        if y < 0 || y >= self.height as i32 {
+            return;
+        }
+
+        for x in x1.max(0)..=x2.min(self.width as i32 - 1) {
+            self.put_pixel(x, y, color);
+        }
+    }
+
+    fn draw_vline(&mut self, x: i32, y1: i32, y2: i32, color: Color) {
+        if x < 0 || x >= self.width as i32 {
+            return;
+        }
+
+        for y in y1.max(0)..=y2.min(self.height as i32 - 1) {
+            self.put_pixel(x, y, color);
+        }
+    }
+
+    fn put_pixel_clipped(&mut self, x: i32, y: i32, color: Color) {
+        if x >= 0 && y >= 0 && x < self.width as i32 && y < self.height as i32 {
+            self.put_pixel(x, y, color);
+        }
+    }
+
+    pub fn fill_disk_subpixel(&mut self, cx: f32, cy: f32, radius: f32, color: Color) {
+        if radius <= 0.0 {
+            return;
+        }
+
+        let min_x = ((cx - radius - 1.0).floor() as i32).max(0);
+        let max_x = ((cx + radius + 1.0).ceil() as i32).min(self.width as i32 - 1);
+        let min_y = ((cy - radius - 1.0).floor() as i32).max(0);
+        let max_y = ((cy + radius + 1.0).ceil() as i32).min(self.height as i32 - 1);
+
+        for y in min_y..=max_y {
+            for x in min_x..=max_x {
+                let dx = x as f32 - cx;
+                let dy = y as f32 - cy;
+                let coverage = (radius + 0.5 - sqrtf(dx * dx + dy * dy)).clamp(0.0, 1.0);
+                if coverage > 0.0 {
+                    self.put_pixel_aa(x, y, color, coverage);
+                }
+            }
+        }
+    }
+
+    pub fn draw_filled_circle(
+        &mut self,
+        cx: f32,
+        cy: f32,
+        radius: f32,
+        fill: Color,
+        stroke: Color,
+        stroke_width: u32,
+    ) {
+        if radius <= 0.0 {
+            return;
+        }
+
+        let inner = radius - stroke_width as f32;
+        let min_x = ((cx - radius - 1.0).floor() as i32).max(0);
+        let max_x = ((cx + radius + 1.0).ceil() as i32).min(self.width as i32 - 1);
+        let min_y = ((cy - radius - 1.0).floor() as i32).max(0);
+        let max_y = ((cy + radius + 1.0).ceil() as i32).min(self.height as i32 - 1);
+
+        for y in min_y..=max_y {
+            for x in min_x..=max_x {
+                let dx = x as f32 - cx;
+                let dy = y as f32 - cy;
+                let dist = sqrtf(dx * dx + dy * dy);
+                let coverage = (radius + 0.5 - dist).clamp(0.0, 1.0);
+                if coverage <= 0.0 {
+                    continue;
+                }
+
+                let stroke_t = if stroke_width == 0 {
+                    0.0
+                } else {
+                    (dist - inner + 0.5).clamp(0.0, 1.0)
+                };
+                self.put_pixel_aa(x, y, fill.lerp(stroke, stroke_t), coverage);
+            }
+        }
+    }
+
+    pub fn fill_circle_gradient(&mut self, center: Point, radius: i32, inner: Color, outer: Color) {
+        self.fill_ellipse_gradient(center, radius, radius, inner, outer);
+    }
+
+    pub fn fill_ellipse_gradient(
+        &mut self,
+        center: Point,
+        rx: i32,
+        ry: i32,
+        inner: Color,
+        outer: Color,
+    ) {
+        if rx <= 0 || ry <= 0 {
+            return;
+        }
+
+        let rx_sq = (rx as u64) * (rx as u64);
+        let ry_sq = (ry as u64) * (ry as u64);
+
+        for dy in -ry..=ry {
+            let y = center.y + dy;
+            if y < 0 || y >= self.height as i32 {
+                continue;
+            }
+
+            let dy_sq = (dy as i64 * dy as i64) as u64;
+            let half = isqrt(((ry_sq - dy_sq) * rx_sq / ry_sq) as u32) as i32;
+            let x1 = (center.x - half).max(0);
+            let x2 = (center.x + half).min(self.width as i32 - 1);
+
+            for x in x1..=x2 {
+                let nx = (x - center.x) as f32 / rx as f32;
+                let ny = dy as f32 / ry as f32;
+                let t = sqrtf(nx * nx + ny * ny);
+                self.put_pixel(x, y, inner.lerp(outer, t));
+            }
+        }
+    }
+
+    pub fn draw_triangle(&mut self, p1: Point, p2: Point, p3: Point, color: Color) {
+        self.draw_line(p1, p2, color);
+        self.draw_line(p2, p3, color);
+        self.draw_line(p3, p1, color);
+    }
+
+    pub fn draw_triangle_aa(&mut self, p1: Point, p2: Point, p3: Point, color: Color) {
+        self.draw_polygon_aa(&[p1, p2, p3], color);
+    }
+
+    pub fn draw_cubic_bezier_aa(
+        &mut self,
+        p0: Point,
+        p1: Point,
+        p2: Point,
+        p3: Point,
+        tolerance: f32,
+        color: Color,
+    ) {
+        let to_f32 = |p: Point| (p.x as f32, p.y as f32);
+        let control = [to_f32(p0), to_f32(p1), to_f32(p2), to_f32(p3)];
+
+        let mut points = alloc::vec![control[0]];
+        flatten_cubic(control, tolerance.max(0.01), 0, &mut points);
+
+        let mut coverage = BTreeMap::new();
+        for pair in points.windows(2) {
+            let ((x0, y0), (x1, y1)) = (pair[0], pair[1]);
+            wu_line(x0, y0, x1, y1, |x, y, alpha| {
+                add_coverage_max(&mut coverage, x, y, alpha)
+            });
+        }
+        self.blend_coverage(&coverage, color);
+    }
+
+    fn blend_coverage(&mut self, coverage: &BTreeMap<(i32, i32), f32>, color: Color) {
+        for (&(x, y), &alpha) in coverage {
+            self.put_pixel_aa(x, y, color, alpha);
+        }
+    }
+
+    pub fn fill_triangle(&mut self, p1: Point, p2: Point, p3: Point, color: Color) {
         let mut points = [p1, p2, p3];
         if points[0].y > points[1].y {
             points.swap(0, 1);
@@ -521,6 +1279,194 @@ impl Sight {
         }
     }
 
+    pub fn draw_polygon(&mut self, points: &[Point], color: Color) {
+        if points.len() < 2 {
+            return;
+        }
+
+        for i in 0..points.len() {
+            self.draw_line(points[i], points[(i + 1) % points.len()], color);
+        }
+    }
+
+    pub fn draw_polygon_aa(&mut self, points: &[Point], color: Color) {
+        let mut vertices: Vec<Point> = Vec::with_capacity(points.len());
+        for &p in points {
+            if vertices.last() != Some(&p) {
+                vertices.push(p);
+            }
+        }
+        while vertices.len() > 1 && vertices.first() == vertices.last() {
+            vertices.pop();
+        }
+        if vertices.len() < 2 {
+            return;
+        }
+
+        let edges = if vertices.len() == 2 {
+            1
+        } else {
+            vertices.len()
+        };
+        let mut coverage = BTreeMap::new();
+        for i in 0..edges {
+            let a = vertices[i];
+            let b = vertices[(i + 1) % vertices.len()];
+            wu_line(
+                a.x as f32,
+                a.y as f32,
+                b.x as f32,
+                b.y as f32,
+                |x, y, alpha| add_coverage(&mut coverage, x, y, alpha),
+            );
+        }
+        self.blend_coverage(&coverage, color);
+    }
+
+    pub fn fill_polygon(&mut self, points: &[Point], color: Color) {
+        if points.len() < 3 {
+            return;
+        }
+
+        let min_y = points.iter().map(|p| p.y).min().unwrap_or(0).max(0);
+        let max_y = points
+            .iter()
+            .map(|p| p.y)
+            .max()
+            .unwrap_or(0)
+            .min(self.height as i32 - 1);
+
+        let mut crossings = Vec::new();
+        for y in min_y..=max_y {
+            crossings.clear();
+            for i in 0..points.len() {
+                let a = points[i];
+                let b = points[(i + 1) % points.len()];
+                let (top, bottom) = if a.y <= b.y { (a, b) } else { (b, a) };
+                if y < top.y || y >= bottom.y {
+                    continue;
+                }
+
+                let t = (y - top.y) as f32 / (bottom.y - top.y) as f32;
+                crossings.push(top.x as f32 + t * (bottom.x - top.x) as f32);
+            }
+            crossings.sort_unstable_by(|a, b| a.total_cmp(b));
+
+            for span in crossings.chunks_exact(2) {
+                let x1 = (span[0].ceil() as i32).max(0);
+                let x2 = (span[1].ceil() as i32).min(self.width as i32);
+                for x in x1..x2 {
+                    self.put_pixel(x, y, color);
+                }
+            }
+        }
+    }
+
+    pub fn fill_polygon_aa(&mut self, points: &[Point], color: Color) {
+        if points.len() < 3 {
+            return;
+        }
+
+        let top = points.iter().map(|p| p.y).min().unwrap_or(0).max(0);
+        let bottom = points
+            .iter()
+            .map(|p| p.y)
+            .max()
+            .unwrap_or(0)
+            .min(self.height as i32);
+        if top >= bottom {
+            return;
+        }
+
+        let stride = self.width as usize + 1;
+        let rows = (bottom - top) as usize;
+        let mut accumulation = alloc::vec![0.0f32; stride * rows];
+
+        for i in 0..points.len() {
+            let a = points[i];
+            let b = points[(i + 1) % points.len()];
+            accumulate_edge(
+                &mut accumulation,
+                stride,
+                top,
+                (a.x as f32, a.y as f32),
+                (b.x as f32, b.y as f32),
+            );
+        }
+
+        for (row, line) in accumulation.chunks_exact(stride).enumerate() {
+            let y = top + row as i32;
+            let mut sum = 0.0;
+            for (x, &delta) in line[..self.width as usize].iter().enumerate() {
+                sum += delta;
+                let alpha = sum.abs().min(1.0);
+                if alpha > 0.0 {
+                    self.put_pixel_aa(x as i32, y, color, alpha);
+                }
+            }
+        }
+    }
+
+    pub fn draw_polygon_rounded(&mut self, points: &[Point], radius: i32, color: Color) {
+        let outline = rounded_polygon_points(points, radius);
+        self.draw_polygon_aa(&outline, color);
+    }
+
+    pub fn fill_polygon_rounded(&mut self, points: &[Point], radius: i32, color: Color) {
+        let outline = rounded_polygon_points(points, radius);
+        self.fill_polygon(&outline, color);
+    }
+
+    pub fn draw_regular_polygon(
+        &mut self,
+        center: Point,
+        radius: i32,
+        sides: u32,
+        rotation: f32,
+        color: Color,
+    ) {
+        let points = regular_polygon_points(center, radius, sides, rotation);
+        self.draw_polygon_aa(&points, color);
+    }
+
+    pub fn fill_regular_polygon(
+        &mut self,
+        center: Point,
+        radius: i32,
+        sides: u32,
+        rotation: f32,
+        color: Color,
+    ) {
+        let points = regular_polygon_points(center, radius, sides, rotation);
+        self.fill_polygon(&points, color);
+    }
+
+    pub fn draw_star(
+        &mut self,
+        center: Point,
+        outer_radius: i32,
+        inner_radius: i32,
+        points: u32,
+        rotation: f32,
+        color: Color,
+    ) {
+        let vertices = star_points(center, outer_radius, inner_radius, points, rotation);
+        self.draw_polygon_aa(&vertices, color);
+    }
+
+    pub fn fill_star(
+        &mut self,
+        center: Point,
+        outer_radius: i32,
+        inner_radius: i32,
+        points: u32,
+        rotation: f32,
+        color: Color,
+    ) {
+        let vertices = star_points(center, outer_radius, inner_radius, points, rotation);
+        self.fill_polygon(&vertices, color);
+    }
+
     pub fn draw_rounded_rect(&mut self, rect: Rect, radius: i32, color: Color) {
         if radius <= 0 {
             self.draw_rect(rect, color);
@@ -546,13 +1492,74 @@ impl Sight {
         );
         self.draw_line(Point::new(x, y + h - r), Point::new(x, y + r), color);
 
-        let pi = 3.14159265359;
+        let pi = core::f32::consts::PI;
         self.draw_arc(Point::new(x + r, y + r), r, pi, pi * 1.5, color);
         self.draw_arc(Point::new(x + w - r, y + r), r, pi * 1.5, pi * 2.0, color);
         self.draw_arc(Point::new(x + w - r, y + h - r), r, 0.0, pi * 0.5, color);
         self.draw_arc(Point::new(x + r, y + h - r), r, pi * 0.5, pi, color);
     }
 
+    pub fn fill_rounded_rect_blended(&mut self, rect: Rect, radius: i32, color: Color) {
+        let Some((x1, y1, x2, y2)) = self.clip_rect(rect) else {
+            return;
+        };
+        let r = (radius.max(0) as f32).min(rect.width.min(rect.height) as f32 / 2.0);
+
+        for y in y1..y2 {
+            for x in x1..x2 {
+                let d = rounded_rect_sdf(x as f32 + 0.5, y as f32 + 0.5, rect, r);
+                let coverage = (0.5 - d).clamp(0.0, 1.0);
+                if coverage > 0.0 {
+                    self.blend_pixel(x, y, color.scale_alpha(coverage));
+                }
+            }
+        }
+    }
+
+    pub fn draw_rounded_rect_thick(
+        &mut self,
+        rect: Rect,
+        radius: i32,
+        thickness: u32,
+        color: Color,
+    ) {
+        if rect.width == 0 || rect.height == 0 || thickness == 0 {
+            return;
+        }
+
+        let max_radius = rect.width.min(rect.height) as f32 / 2.0;
+        let r = (radius.max(0) as f32).min(max_radius);
+        let t = (thickness as f32).min(max_radius);
+
+        let x1 = rect.x.max(0);
+        let y1 = rect.y.max(0);
+        let x2 = (rect.x + rect.width as i32).min(self.width as i32);
+        let y2 = (rect.y + rect.height as i32).min(self.height as i32);
+
+        for y in y1..y2 {
+            for x in x1..x2 {
+                let d = rounded_rect_sdf(x as f32 + 0.5, y as f32 + 0.5, rect, r);
+                let coverage = (0.5 - d).clamp(0.0, 1.0) * (d + t + 0.5).clamp(0.0, 1.0);
+                if coverage > 0.0 {
+                    self.put_pixel_aa(x, y, color, coverage);
+                }
+            }
+        }
+    }
+
+    pub fn draw_focus_ring(&mut self, rect: Rect, radius: i32, thickness: u32, color: Color) {
+        const GAP: i32 = 2;
+        let spread = GAP + thickness as i32;
+        let ring = Rect::new(
+            rect.x - spread,
+            rect.y - spread,
+            rect.width + 2 * spread as u32,
+            rect.height + 2 * spread as u32,
+        );
+
+        self.draw_rounded_rect_thick(ring, radius.max(0) + spread, thickness, color);
+    }
+
     pub fn draw_arc(
         &mut self,
         center: Point,
@@ -562,7 +1569,7 @@ impl Sight {
         color: Color,
     ) {
         let r = radius as f32;
-        let pi2 = 6.28318530718;
+        let pi2 = core::f32::consts::TAU;
 
         let start = start_angle;
         let mut end = end_angle;
@@ -574,149 +1581,2285 @@ impl Sight {
         let angle_range = end - start;
         let circumference = pi2 * r;
         let arc_length = (angle_range / pi2) * circumference;
-        let steps = (arc_length * 2.0) as i32;
-        let steps = steps.max(30).min(1000);
+        let steps = ((arc_length * 2.0) as i32).max(30);
 
+        let mut last = None;
         for i in 0..=steps {
             let t = i as f32 / steps as f32;
             let angle = start + angle_range * t;
             let x = center.x + (cosf(angle) * r) as i32;
             let y = center.y + (sinf(angle) * r) as i32;
-            self.put_pixel(x, y, color);
-        }
-    }
-
-    pub fn fill_gradient_h(&mut self, rect: Rect, start_color: Color, end_color: Color) {
-        let x1 = rect.x.max(0);
-        let x2 = (rect.x + rect.width as i32).min(self.width as i32);
-        let y1 = rect.y.max(0);
-        let y2 = (rect.y + rect.height as i32).min(self.height as i32);
-
-        for x in x1..x2 {
-            let t = (x - x1) as f32 / (x2 - x1) as f32;
-            let color = start_color.lerp(end_color, t);
-            for y in y1..y2 {
+            if last != Some((x, y)) {
                 self.put_pixel(x, y, color);
+                last = Some((x, y));
             }
         }
     }
 
-    pub fn fill_gradient_v(&mut self, rect: Rect, start_color: Color, end_color: Color) {
-        let x1 = rect.x.max(0);
-        let x2 = (rect.x + rect.width as i32).min(self.width as i32);
-        let y1 = rect.y.max(0);
-        let y2 = (rect.y + rect.height as i32).min(self.height as i32);
+    pub fn draw_arc_aa(
+        &mut self,
+        center: Point,
+        radius: i32,
+        start_angle: f32,
+        end_angle: f32,
+        color: Color,
+    ) {
+        if radius <= 0 {
+            return;
+        }
 
-        for y in y1..y2 {
-            let t = (y - y1) as f32 / (y2 - y1) as f32;
-            let color = start_color.lerp(end_color, t);
-            for x in x1..x2 {
-                self.put_pixel(x, y, color);
-            }
+        let r = radius as f32;
+        let pi2 = core::f32::consts::TAU;
+
+        let mut end = end_angle;
+        while end < start_angle {
+            end += pi2;
         }
-    }
+        let sweep = end - start_angle;
 
-    pub fn draw_bmp(&mut self, bmp: &bmp::BmpImage, x: i32, y: i32) {
-        for iy in 0..bmp.height {
-            for ix in 0..bmp.width {
-                let pixel_idx = ((iy * bmp.width + ix) * 4) as usize;
-                if pixel_idx + 3 < bmp.data.len() {
-                    let color = Color::rgba(
-                        bmp.data[pixel_idx + 2],
-                        bmp.data[pixel_idx + 1],
-                        bmp.data[pixel_idx],
-                        bmp.data[pixel_idx + 3],
-                    );
-                    if color.a > 0 {
-                        self.put_pixel(x + ix as i32, y + iy as i32, color);
+        for y in (center.y - radius - 1)..=(center.y + radius + 1) {
+            for x in (center.x - radius - 1)..=(center.x + radius + 1) {
+                let dx = (x - center.x) as f32;
+                let dy = (y - center.y) as f32;
+                let coverage = 1.0 - (sqrtf(dx * dx + dy * dy) - r).abs();
+                if coverage <= 0.0 {
+                    continue;
+                }
+
+                if sweep < pi2 {
+                    let mut offset = fmodf(atan2f(dy, dx) - start_angle, pi2);
+                    if offset < 0.0 {
+                        offset += pi2;
+                    }
+                    if offset > sweep {
+                        continue;
                     }
                 }
+
+                self.put_pixel_aa(x, y, color, coverage);
             }
         }
     }
 
-    pub fn present(&mut self) -> Result<(), &'static str> {
-        syscall3(2, 1, b"present() called, dirty=".as_ptr() as u64, 24);
-        if self.dirty {
-            syscall3(2, 1, b"true\n".as_ptr() as u64, 5);
+    pub fn draw_arc_gauge(
+        &mut self,
+        center: Point,
+        radius: i32,
+        thickness: u32,
+        start_angle: f32,
+        end_angle: f32,
+        color: Color,
+    ) {
+        if radius <= 0 || thickness == 0 {
+            return;
+        }
+
+        let pi2 = core::f32::consts::TAU;
+        let half = (thickness as f32).min(radius as f32) / 2.0;
+        let mid = radius as f32 - half;
+
+        let mut end = end_angle;
+        while end < start_angle {
+            end += pi2;
+        }
+        let sweep = end - start_angle;
+        let caps = [
+            (cosf(start_angle) * mid, sinf(start_angle) * mid),
+            (cosf(end) * mid, sinf(end) * mid),
+        ];
+
+        let min_x = (center.x - radius - 1).max(0);
+        let max_x = (center.x + radius + 1).min(self.width as i32 - 1);
+        let min_y = (center.y - radius - 1).max(0);
+        let max_y = (center.y + radius + 1).min(self.height as i32 - 1);
+
+        for y in min_y..=max_y {
+            for x in min_x..=max_x {
+                let dx = (x - center.x) as f32;
+                let dy = (y - center.y) as f32;
+
+                let mut offset = fmodf(atan2f(dy, dx) - start_angle, pi2);
+                if offset < 0.0 {
+                    offset += pi2;
+                }
+
+                let d = if sweep >= pi2 || offset <= sweep {
+                    (sqrtf(dx * dx + dy * dy) - mid).abs() - half
+                } else {
+                    caps.iter()
+                        .map(|&(ex, ey)| sqrtf((dx - ex) * (dx - ex) + (dy - ey) * (dy - ey)))
+                        .fold(f32::INFINITY, f32::min)
+                        - half
+                };
+
+                let coverage = (0.5 - d).clamp(0.0, 1.0);
+                if coverage > 0.0 {
+                    self.put_pixel_aa(x, y, color, coverage);
+                }
+            }
+        }
+    }
+
+    pub fn draw_ellipse_aa(&mut self, center: Point, rx: i32, ry: i32, color: Color) {
+        if rx <= 0 || ry <= 0 {
+            return;
+        }
+
+        for y in (center.y - ry - 1)..=(center.y + ry + 1) {
+            for x in (center.x - rx - 1)..=(center.x + rx + 1) {
+                let d = ellipse_distance(
+                    (x - center.x) as f32,
+                    (y - center.y) as f32,
+                    rx as f32,
+                    ry as f32,
+                );
+                let coverage = 1.0 - d.abs();
+                if coverage > 0.0 {
+                    self.put_pixel_aa(x, y, color, coverage);
+                }
+            }
+        }
+    }
+
+    pub fn fill_ellipse_aa(&mut self, center: Point, rx: i32, ry: i32, color: Color) {
+        if rx <= 0 || ry <= 0 {
+            return;
+        }
+
+        let min_x = (center.x - rx - 1).max(0);
+        let max_x = (center.x + rx + 1).min(self.width as i32 - 1);
+        let min_y = (center.y - ry - 1).max(0);
+        let max_y = (center.y + ry + 1).min(self.height as i32 - 1);
+
+        for y in min_y..=max_y {
+            for x in min_x..=max_x {
+                let d = ellipse_distance(
+                    (x - center.x) as f32,
+                    (y - center.y) as f32,
+                    rx as f32,
+                    ry as f32,
+                );
+                let coverage = (0.5 - d).clamp(0.0, 1.0);
+                if coverage > 0.0 {
+                    self.put_pixel_aa(x, y, color, coverage);
+                }
+            }
+        }
+    }
+
+    pub fn fill_gradient_h(&mut self, rect: Rect, start_color: Color, end_color: Color) {
+        let over = start_color.a < 255 || end_color.a < 255;
+        self.gradient_h(rect, start_color, end_color, over);
+    }
+
+    pub fn fill_gradient_h_over(&mut self, rect: Rect, start_color: Color, end_color: Color) {
+        self.gradient_h(rect, start_color, end_color, true);
+    }
+
+    pub fn fill_gradient_v(&mut self, rect: Rect, start_color: Color, end_color: Color) {
+        let over = start_color.a < 255 || end_color.a < 255;
+        self.gradient_v(rect, start_color, end_color, over);
+    }
+
+    pub fn fill_gradient_v_over(&mut self, rect: Rect, start_color: Color, end_color: Color) {
+        self.gradient_v(rect, start_color, end_color, true);
+    }
+
+    fn gradient_h(&mut self, rect: Rect, start_color: Color, end_color: Color, over: bool) {
+        let x1 = rect.x.max(0);
+        let x2 = (rect.x + rect.width as i32).min(self.width as i32);
+        let y1 = rect.y.max(0);
+        let y2 = (rect.y + rect.height as i32).min(self.height as i32);
+
+        for x in x1..x2 {
+            let t = (x - x1) as f32 / (x2 - x1) as f32;
+            let color = start_color.lerp(end_color, t);
+            for y in y1..y2 {
+                self.put_gradient_pixel(x, y, color, over);
+            }
+        }
+    }
+
+    fn gradient_v(&mut self, rect: Rect, start_color: Color, end_color: Color, over: bool) {
+        let x1 = rect.x.max(0);
+        let x2 = (rect.x + rect.width as i32).min(self.width as i32);
+        let y1 = rect.y.max(0);
+        let y2 = (rect.y + rect.height as i32).min(self.height as i32);
+
+        for y in y1..y2 {
+            let t = (y - y1) as f32 / (y2 - y1) as f32;
+            let color = start_color.lerp(end_color, t);
+            for x in x1..x2 {
+                self.put_gradient_pixel(x, y, color, over);
+            }
+        }
+    }
+
+    fn put_gradient_pixel(&mut self, x: i32, y: i32, color: Color, over: bool) {
+        if over {
+            self.blend_pixel(x, y, color);
         } else {
-            syscall3(2, 1, b"false\n".as_ptr() as u64, 6);
+            self.put_pixel(x, y, color);
         }
+    }
 
-        if !self.dirty {
-            return Ok(());
+    pub fn fill_gradient_linear(
+        &mut self,
+        rect: Rect,
+        start: Point,
+        end: Point,
+        c1: Color,
+        c2: Color,
+    ) {
+        let x1 = rect.x.max(0);
+        let x2 = (rect.x + rect.width as i32).min(self.width as i32);
+        let y1 = rect.y.max(0);
+        let y2 = (rect.y + rect.height as i32).min(self.height as i32);
+
+        let dx = (end.x - start.x) as f32;
+        let dy = (end.y - start.y) as f32;
+        let length_sq = dx * dx + dy * dy;
+        let over = c1.a < 255 || c2.a < 255;
+
+        for y in y1..y2 {
+            for x in x1..x2 {
+                let t = if length_sq == 0.0 {
+                    0.0
+                } else {
+                    ((x - start.x) as f32 * dx + (y - start.y) as f32 * dy) / length_sq
+                };
+                self.put_gradient_pixel(x, y, c1.lerp(c2, t), over);
+            }
         }
+    }
 
-        syscall3(2, 1, b"Calling GPU flush...\n".as_ptr() as u64, 21);
-        let result = syscall3(SYS_GPU_FLUSH, 0, 0, 0);
+    pub fn flood_fill(&mut self, x: i32, y: i32, fill: Color) {
+        self.flood_fill_tolerance(x, y, fill, 0.0);
+    }
 
-        if result == u64::MAX {
-            syscall3(2, 1, b"Flush failed!\n".as_ptr() as u64, 14);
-            return Err("Failed to flush GPU");
+    pub fn flood_fill_tolerance(&mut self, x: i32, y: i32, fill: Color, tolerance: f32) {
+        let width = self.width as i32;
+        let height = self.height as i32;
+        if x < 0 || y < 0 || x >= width || y >= height {
+            return;
         }
 
-        syscall3(2, 1, b"Flush succeeded!\n".as_ptr() as u64, 17);
-        self.dirty = false;
-        Ok(())
+        let seed = self.read_pixel(x, y);
+        let mut visited = alloc::vec![false; (self.width * self.height) as usize];
+        let mut stack = alloc::vec![(x, y)];
+        visited[(y * width + x) as usize] = true;
+
+        while let Some((x, y)) = stack.pop() {
+            self.put_pixel(x, y, fill);
+
+            for (nx, ny) in [(x - 1, y), (x + 1, y), (x, y - 1), (x, y + 1)] {
+                if nx < 0 || ny < 0 || nx >= width || ny >= height {
+                    continue;
+                }
+
+                let index = (ny * width + nx) as usize;
+                if visited[index] || self.read_pixel(nx, ny).distance(seed) > tolerance {
+                    continue;
+                }
+                visited[index] = true;
+                stack.push((nx, ny));
+            }
+        }
     }
 
-    pub fn force_present(&mut self) -> Result<(), &'static str> {
-        let result = syscall3(SYS_GPU_FLUSH, 0, 0, 0);
-        if result == u64::MAX {
-            return Err("Failed to flush GPU");
+    pub fn scroll(&mut self, dx: i32, dy: i32, fill: Color) {
+        let dy = if self.y_up { -dy } else { dy };
+        if dx.unsigned_abs() >= self.width || dy.unsigned_abs() >= self.height {
+            self.clear(fill);
+            return;
         }
 
-        self.dirty = false;
-        Ok(())
+        let width = self.width as usize;
+        let height = self.height as usize;
+        let pixel = fill.to_u32();
+        let pixels = self.pixels_mut();
+
+        let shift = dy.unsigned_abs() as usize;
+        if dy > 0 {
+            pixels.copy_within(0..(height - shift) * width, shift * width);
+            pixels[..shift * width].fill(pixel);
+        } else if dy < 0 {
+            pixels.copy_within(shift * width.., 0);
+            pixels[(height - shift) * width..].fill(pixel);
+        }
+
+        let shift = dx.unsigned_abs() as usize;
+        if dx != 0 {
+            for row in pixels.chunks_exact_mut(width) {
+                if dx > 0 {
+                    row.copy_within(0..width - shift, shift);
+                    row[..shift].fill(pixel);
+                } else {
+                    row.copy_within(shift.., 0);
+                    row[width - shift..].fill(pixel);
+                }
+            }
+        }
+
+        self.mark_dirty(self.width * self.height);
     }
 
-    pub fn test_write(&mut self) {
-        let ptr_val = self.fb as u64;
-        syscall3(2, 1, b"test_write: fb=".as_ptr() as u64, 15);
-        let mut buf = [0u8; 18];
-        buf[0] = b'0';
-        buf[1] = b'x';
-        for i in 0..16 {
-            let nibble = ((ptr_val >> (60 - i * 4)) & 0xF) as u8;
-            buf[i + 2] = if nibble < 10 {
-                b'0' + nibble
-            } else {
-                b'a' + nibble - 10
-            };
+    pub fn fade_region(&mut self, rect: Rect, toward: Color, t: f32) {
+        let x1 = rect.x.max(0);
+        let y1 = rect.y.max(0);
+        let x2 = (rect.x + rect.width as i32).min(self.width as i32);
+        let y2 = (rect.y + rect.height as i32).min(self.height as i32);
+
+        for y in y1..y2 {
+            for x in x1..x2 {
+                let color = self.read_pixel(x, y).lerp(toward, t);
+                self.put_pixel(x, y, color);
+            }
         }
-        syscall3(2, 1, buf.as_ptr() as u64, 18);
-        syscall3(2, 1, b"\n".as_ptr() as u64, 1);
+    }
 
-        unsafe {
-            *self.fb = 0xFFFFFFFF;
-            *self.fb.add(1) = 0xFF0000FF;
-            *self.fb.add(2) = 0xFF00FF00;
-            *self.fb.add(3) = 0xFFFF0000;
+    pub fn gradient_map(&mut self, rect: Rect, gradient: &Gradient) {
+        let x1 = rect.x.max(0);
+        let y1 = rect.y.max(0);
+        let x2 = (rect.x + rect.width as i32).min(self.width as i32);
+        let y2 = (rect.y + rect.height as i32).min(self.height as i32);
 
-            for i in 0..100 {
-                *self.fb.add(i) = 0xFFFFFFFF;
+        for y in y1..y2 {
+            for x in x1..x2 {
+                let luminance = self.read_pixel(x, y).luminance() as f32 / 255.0;
+                self.put_pixel(x, y, gradient.sample(luminance));
             }
         }
-        self.dirty = true;
     }
-}
 
-trait FloatExt {
-    fn fract(self) -> Self;
-    fn floor(self) -> Self;
-    fn round(self) -> Self;
-}
+    pub fn histogram(&self, rect: Rect) -> [[u32; 256]; 3] {
+        let mut bins = [[0u32; 256]; 3];
+        let Some((x1, y1, x2, y2)) = self.clip_rect(rect) else {
+            return bins;
+        };
 
-impl FloatExt for f32 {
-    fn fract(self) -> f32 {
-        self - self.floor()
+        for y in y1..y2 {
+            for x in x1..x2 {
+                let color = self.read_pixel(x, y);
+                bins[0][color.r as usize] += 1;
+                bins[1][color.g as usize] += 1;
+                bins[2][color.b as usize] += 1;
+            }
+        }
+
+        bins
     }
 
-    fn floor(self) -> f32 {
-        libm::floorf(self)
+    pub fn average_color(&self, rect: Rect) -> Color {
+        let Some((x1, y1, x2, y2)) = self.clip_rect(rect) else {
+            return Color::TRANSPARENT;
+        };
+
+        let mut sums = [0u64; 4];
+        for y in y1..y2 {
+            for x in x1..x2 {
+                let color = self.read_pixel(x, y);
+                for (sum, channel) in sums.iter_mut().zip([color.r, color.g, color.b, color.a]) {
+                    *sum += channel as u64;
+                }
+            }
+        }
+
+        let count = ((x2 - x1) * (y2 - y1)) as u64;
+        let [r, g, b, a] = sums.map(|sum| ((sum + count / 2) / count) as u8);
+        Color::rgba(r, g, b, a)
     }
 
-    fn round(self) -> f32 {
-        libm::roundf(self)
+    pub fn fill_rect_pattern(&mut self, rect: Rect, pattern: &bmp::BmpImage, offset: Point) {
+        if pattern.width == 0 || pattern.height == 0 {
+            return;
+        }
+
+        let x1 = rect.x.max(0);
+        let y1 = rect.y.max(0);
+        let x2 = (rect.x + rect.width as i32).min(self.width as i32);
+        let y2 = (rect.y + rect.height as i32).min(self.height as i32);
+
+        for y in y1..y2 {
+            let py = (y - rect.y - offset.y).rem_euclid(pattern.height as i32) as u32;
+            for x in x1..x2 {
+                let px = (x - rect.x - offset.x).rem_euclid(pattern.width as i32) as u32;
+                let Some(color) = pattern.get_pixel(px, py) else {
+                    continue;
+                };
+
+                match color.a {
+                    0 => {}
+                    255 => self.put_pixel(x, y, color),
+                    _ => self.blend_pixel(x, y, color),
+                }
+            }
+        }
+    }
+
+    pub fn draw_bmp(&mut self, bmp: &bmp::BmpImage, x: i32, y: i32) {
+        self.draw_image(bmp, x, y);
+    }
+
+    pub fn draw_image(&mut self, img: &impl Drawable, x: i32, y: i32) {
+        for iy in 0..img.height() {
+            for ix in 0..img.width() {
+                let color = img.pixel(ix, iy);
+                if color.a > 0 {
+                    self.put_pixel_clipped(x + ix as i32, y + iy as i32, color);
+                }
+            }
+        }
+    }
+
+    pub fn draw_image_keyed(&mut self, img: &impl Drawable, x: i32, y: i32, key: Color) {
+        for iy in 0..img.height() {
+            for ix in 0..img.width() {
+                let color = img.pixel(ix, iy);
+                if color != key {
+                    self.put_pixel_clipped(x + ix as i32, y + iy as i32, color);
+                }
+            }
+        }
+    }
+
+    pub fn draw_image_blended(&mut self, img: &impl Drawable, x: i32, y: i32) {
+        for iy in 0..img.height() {
+            for ix in 0..img.width() {
+                self.blend_pixel(x + ix as i32, y + iy as i32, img.pixel(ix, iy));
+            }
+        }
+    }
+
+    pub fn draw_image_scaled(&mut self, img: &impl Drawable, dst: Rect) {
+        if img.width() == 0 || img.height() == 0 {
+            return;
+        }
+        let Some((x1, y1, x2, y2)) = self.clip_rect(dst) else {
+            return;
+        };
+
+        for y in y1..y2 {
+            let src_y = ((y - dst.y) as u64 * img.height() as u64 / dst.height as u64) as u32;
+            for x in x1..x2 {
+                let src_x = ((x - dst.x) as u64 * img.width() as u64 / dst.width as u64) as u32;
+                let color = img.pixel(src_x, src_y);
+                if color.a > 0 {
+                    self.put_pixel(x, y, color);
+                }
+            }
+        }
+    }
+
+    pub fn draw_bmp_rounded(&mut self, bmp: &bmp::BmpImage, rect: Rect, radius: i32) {
+        if bmp.width == 0 || bmp.height == 0 || rect.width == 0 || rect.height == 0 {
+            return;
+        }
+
+        let r = (radius.max(0) as f32).min(rect.width.min(rect.height) as f32 / 2.0);
+        let x1 = rect.x.max(0);
+        let y1 = rect.y.max(0);
+        let x2 = (rect.x + rect.width as i32).min(self.width as i32);
+        let y2 = (rect.y + rect.height as i32).min(self.height as i32);
+
+        for y in y1..y2 {
+            for x in x1..x2 {
+                if rounded_rect_sdf(x as f32 + 0.5, y as f32 + 0.5, rect, r) > 0.0 {
+                    continue;
+                }
+
+                let src_x = ((x - rect.x) as u64 * bmp.width as u64 / rect.width as u64) as u32;
+                let src_y = ((y - rect.y) as u64 * bmp.height as u64 / rect.height as u64) as u32;
+                if let Some(color) = bmp.get_pixel(src_x, src_y).filter(|c| c.a > 0) {
+                    self.put_pixel(x, y, color);
+                }
+            }
+        }
+    }
+
+    pub fn draw_nine_slice(
+        &mut self,
+        bmp: &bmp::BmpImage,
+        (left, top, right, bottom): (u32, u32, u32, u32),
+        dst: Rect,
+    ) {
+        if left.checked_add(right).is_none_or(|w| w > bmp.width)
+            || top.checked_add(bottom).is_none_or(|h| h > bmp.height)
+        {
+            return;
+        }
+        let Some((x1, y1, x2, y2)) = self.clip_rect(dst) else {
+            return;
+        };
+
+        for y in y1..y2 {
+            let src_y = nine_slice_source((y - dst.y) as u32, dst.height, bmp.height, top, bottom);
+            for x in x1..x2 {
+                let src_x =
+                    nine_slice_source((x - dst.x) as u32, dst.width, bmp.width, left, right);
+                if let Some(color) = bmp.get_pixel(src_x, src_y).filter(|c| c.a > 0) {
+                    self.put_pixel(x, y, color);
+                }
+            }
+        }
+    }
+
+    /// Adds one frame duration to the rolling window behind `stats().average_fps`.
+    /// sOS exposes no clock to this crate, so `present` cannot time frames itself;
+    /// callers measure each frame and report it here.
+    pub fn record_frame_time(&mut self, frame_ms: f32) {
+        self.frame_times[self.frame_count % FRAME_HISTORY] = frame_ms;
+        self.frame_count += 1;
+    }
+
+    /// `pixels_written` is counted automatically and covers the last presented frame.
+    /// `last_frame_ms` and `average_fps` only reflect durations passed to
+    /// `record_frame_time` and stay zero if it is never called.
+    pub fn stats(&self) -> FrameStats {
+        let recorded = self.frame_count.min(FRAME_HISTORY);
+        let last_frame_ms = match self.frame_count {
+            0 => 0.0,
+            n => self.frame_times[(n - 1) % FRAME_HISTORY],
+        };
+
+        FrameStats {
+            last_frame_ms,
+            average_fps: average_fps(&self.frame_times[..recorded]),
+            pixels_written: self.last_pixels_written,
+        }
+    }
+
+    fn mark_dirty(&mut self, pixels: u32) {
+        self.dirty = true;
+        self.pixels_written = self.pixels_written.saturating_add(pixels);
+    }
+
+    fn finish_frame(&mut self) {
+        self.last_pixels_written = self.pixels_written;
+        self.pixels_written = 0;
+        self.dirty = false;
+    }
+
+    pub fn needs_present(&self) -> bool {
+        self.dirty
+    }
+
+    pub fn present(&mut self) -> Result<(), &'static str> {
+        syscall3(2, 1, b"present() called, dirty=".as_ptr() as u64, 24);
+        if self.dirty {
+            syscall3(2, 1, b"true\n".as_ptr() as u64, 5);
+        } else {
+            syscall3(2, 1, b"false\n".as_ptr() as u64, 6);
+        }
+
+        if !self.dirty {
+            return Ok(());
+        }
+
+        self.check_framebuffer()?;
+
+        syscall3(2, 1, b"Calling GPU flush...\n".as_ptr() as u64, 21);
+        let result = syscall3(SYS_GPU_FLUSH, 0, 0, 0);
+
+        if result == u64::MAX {
+            syscall3(2, 1, b"Flush failed!\n".as_ptr() as u64, 14);
+            return Err("Failed to flush GPU");
+        }
+
+        syscall3(2, 1, b"Flush succeeded!\n".as_ptr() as u64, 17);
+        self.finish_frame();
+        Ok(())
+    }
+
+    pub fn force_present(&mut self) -> Result<(), &'static str> {
+        self.check_framebuffer()?;
+
+        let result = syscall3(SYS_GPU_FLUSH, 0, 0, 0);
+        if result == u64::MAX {
+            return Err("Failed to flush GPU");
+        }
+
+        self.finish_frame();
+        Ok(())
+    }
+
+    /// Scales the top-left `width / factor` x `height / factor` logical region up to the
+    /// whole screen and flushes it. The upscaled output replaces the framebuffer contents,
+    /// so that region must be fully redrawn before the next call that follows new drawing.
+    /// `factor` must be between 1 and the smaller screen dimension.
+    pub fn present_crt(&mut self, factor: u32, scanline_strength: f32) -> Result<(), &'static str> {
+        if factor == 0 || factor > self.width.min(self.height) {
+            return Err("Invalid CRT factor");
+        }
+
+        if self.dirty {
+            self.upscale_scanlines(factor, scanline_strength.clamp(0.0, 1.0));
+        }
+        self.force_present()
+    }
+
+    fn upscale_scanlines(&mut self, factor: u32, strength: f32) {
+        let width = self.width as usize;
+        let factor = factor as usize;
+        let src_width = width / factor;
+        let src_height = self.height as usize / factor;
+        if src_width == 0 || src_height == 0 {
+            return;
+        }
+
+        let mut source = Vec::with_capacity(src_width * src_height);
+        for y in 0..src_height {
+            let row = self.native_y(y as i32) as usize * width;
+            source.extend_from_slice(&self.buffer()[row..row + src_width]);
+        }
+
+        let rows: Vec<usize> = (0..self.height as i32)
+            .map(|y| self.native_y(y) as usize * width)
+            .collect();
+        let pixels = self.pixels_mut();
+
+        for (y, row) in rows.into_iter().enumerate() {
+            let src_row = (y / factor).min(src_height - 1) * src_width;
+            for x in 0..width {
+                let mut pixel = source[src_row + (x / factor).min(src_width - 1)];
+                if y % 2 == 1 && strength > 0.0 {
+                    let color = Color::from_u32(pixel);
+                    pixel = color
+                        .lerp(Color::BLACK.with_alpha(color.a), strength)
+                        .to_u32();
+                }
+                pixels[row + x] = pixel;
+            }
+        }
+        self.mark_dirty(self.width * self.height);
+    }
+
+    fn check_framebuffer(&self) -> Result<(), &'static str> {
+        if self.fb.is_null() {
+            return Err("Framebuffer not mapped");
+        }
+        if self.width == 0 || self.height == 0 {
+            return Err("Invalid framebuffer size");
+        }
+        Ok(())
+    }
+
+    pub fn test_write(&mut self) {
+        let ptr_val = self.fb as u64;
+        syscall3(2, 1, b"test_write: fb=".as_ptr() as u64, 15);
+        let mut buf = [0u8; 18];
+        buf[0] = b'0';
+        buf[1] = b'x';
+        for i in 0..16 {
+            let nibble = ((ptr_val >> (60 - i * 4)) & 0xF) as u8;
+            buf[i + 2] = if nibble < 10 {
+                b'0' + nibble
+            } else {
+                b'a' + nibble - 10
+            };
+        }
+        syscall3(2, 1, buf.as_ptr() as u64, 18);
+        syscall3(2, 1, b"\n".as_ptr() as u64, 1);
+
+        unsafe {
+            *self.fb = 0xFFFFFFFF;
+            *self.fb.add(1) = 0xFF0000FF;
+            *self.fb.add(2) = 0xFF00FF00;
+            *self.fb.add(3) = 0xFFFF0000;
+
+            for i in 0..100 {
+                *self.fb.add(i) = 0xFFFFFFFF;
+            }
+        }
+        self.dirty = true;
+    }
+}
+
+impl Drawable for Sight {
+    fn width(&self) -> u32 {
+        self.width
+    }
+
+    fn height(&self) -> u32 {
+        self.height
+    }
+
+    fn pixel(&self, x: u32, y: u32) -> Color {
+        self.get_pixel(x as i32, y as i32)
+            .unwrap_or(Color::TRANSPARENT)
+    }
+}
+
+fn wu_line<F>(mut x0: f32, mut y0: f32, mut x1: f32, mut y1: f32, mut plot: F)
+where
+    F: FnMut(i32, i32, f32),
+{
+    let steep = (y1 - y0).abs() > (x1 - x0).abs();
+
+    if steep {
+        core::mem::swap(&mut x0, &mut y0);
+        core::mem::swap(&mut x1, &mut y1);
+    }
+
+    if x0 > x1 {
+        core::mem::swap(&mut x0, &mut x1);
+        core::mem::swap(&mut y0, &mut y1);
+    }
+
+    let mut emit = |x: i32, y: i32, alpha: f32| {
+        if alpha <= 0.0 {
+            return;
+        }
+        if steep {
+            plot(y, x, alpha);
+        } else {
+            plot(x, y, alpha);
+        }
+    };
+
+    let dx = x1 - x0;
+    let dy = y1 - y0;
+    let gradient = if dx == 0.0 { 1.0 } else { dy / dx };
+
+    let xend = x0.round();
+    let yend = y0 + gradient * (xend - x0);
+    let xgap = 1.0 - (x0 - xend).abs();
+    let xpxl1 = xend as i32;
+    let ypxl1 = yend.floor() as i32;
+
+    emit(xpxl1, ypxl1, (1.0 - yend.fpart()) * xgap);
+    emit(xpxl1, ypxl1 + 1, yend.fpart() * xgap);
+
+    let mut intery = yend + gradient;
+
+    let xend = x1.round();
+    let yend = y1 + gradient * (xend - x1);
+    let xgap = 1.0 - (x1 - xend).abs();
+    let xpxl2 = xend as i32;
+    let ypxl2 = yend.floor() as i32;
+
+    if xpxl2 == xpxl1 {
+        return;
+    }
+
+    emit(xpxl2, ypxl2, (1.0 - yend.fpart()) * xgap);
+    emit(xpxl2, ypxl2 + 1, yend.fpart() * xgap);
+
+    for x in (xpxl1 + 1)..xpxl2 {
+        let y = intery.floor() as i32;
+        let frac = intery.fpart();
+
+        emit(x, y, 1.0 - frac);
+        emit(x, y + 1, frac);
+
+        intery += gradient;
+    }
+}
+
+fn add_coverage(coverage: &mut BTreeMap<(i32, i32), f32>, x: i32, y: i32, alpha: f32) {
+    if alpha <= 0.0 {
+        return;
+    }
+    let entry = coverage.entry((x, y)).or_insert(0.0);
+    *entry = (*entry + alpha).min(1.0);
+}
+
+fn accumulate_edge(
+    accumulation: &mut [f32],
+    stride: usize,
+    top: i32,
+    start: (f32, f32),
+    end: (f32, f32),
+) {
+    if start.1 == end.1 {
+        return;
+    }
+
+    let (direction, (x0, y0), (x1, y1)) = if start.1 < end.1 {
+        (1.0, start, end)
+    } else {
+        (-1.0, end, start)
+    };
+
+    let rows = (accumulation.len() / stride) as i32;
+    let dxdy = (x1 - x0) / (y1 - y0);
+    let first = (y0.floor() as i32).max(top);
+    let last = (y1.ceil() as i32).min(top + rows);
+
+    let mut add = |row: i32, x: i32, value: f32| {
+        let column = x.max(0) as usize;
+        if column < stride {
+            accumulation[(row - top) as usize * stride + column] += value;
+        }
+    };
+
+    for y in first..last {
+        let row_top = (y as f32).max(y0);
+        let row_bottom = ((y + 1) as f32).min(y1);
+        let dy = row_bottom - row_top;
+        let x = x0 + (row_top - y0) * dxdy;
+        let x_next = x + dxdy * dy;
+        let d = dy * direction;
+
+        let (left, right) = if x < x_next { (x, x_next) } else { (x_next, x) };
+        let left_floor = left.floor();
+        let left_i = left_floor as i32;
+        let right_i = right.ceil() as i32;
+
+        if right_i <= left_i + 1 {
+            let mid = 0.5 * (x + x_next) - left_floor;
+            add(y, left_i, d - d * mid);
+            add(y, left_i + 1, d * mid);
+            continue;
+        }
+
+        let inv_width = 1.0 / (right - left);
+        let left_frac = left - left_floor;
+        let first_area = 0.5 * inv_width * (1.0 - left_frac) * (1.0 - left_frac);
+        let right_frac = right - right_i as f32 + 1.0;
+        let last_area = 0.5 * inv_width * right_frac * right_frac;
+
+        add(y, left_i, d * first_area);
+        if right_i == left_i + 2 {
+            add(y, left_i + 1, d * (1.0 - first_area - last_area));
+        } else {
+            let second_area = inv_width * (1.5 - left_frac);
+            add(y, left_i + 1, d * (second_area - first_area));
+            let (start, stop) = (left_i + 2, right_i - 1);
+            let clipped = (stop.min(0) - start).max(0);
+            add(y, 0, d * inv_width * clipped as f32);
+            for xi in start.max(0)..stop.min(stride as i32) {
+                add(y, xi, d * inv_width);
+            }
+            let before_last = second_area + (right_i - left_i - 3) as f32 * inv_width;
+            add(y, right_i - 1, d * (1.0 - before_last - last_area));
+        }
+        add(y, right_i, d * last_area);
+    }
+}
+
+fn add_coverage_max(coverage: &mut BTreeMap<(i32, i32), f32>, x: i32, y: i32, alpha: f32) {
+    let entry = coverage.entry((x, y)).or_insert(0.0);
+    *entry = entry.max(alpha.min(1.0));
+}
+
+fn flatten_cubic(p: [(f32, f32); 4], tolerance: f32, depth: u32, out: &mut Vec<(f32, f32)>) {
+    const MAX_DEPTH: u32 = 16;
+
+    let [(x0, y0), (x1, y1), (x2, y2), (x3, y3)] = p;
+    let (dx, dy) = (x3 - x0, y3 - y0);
+    let length = sqrtf(dx * dx + dy * dy);
+    let deviation = |x: f32, y: f32| {
+        if length == 0.0 {
+            sqrtf((x - x0) * (x - x0) + (y - y0) * (y - y0))
+        } else {
+            ((x - x0) * dy - (y - y0) * dx).abs() / length
+        }
+    };
+
+    if depth >= MAX_DEPTH || deviation(x1, y1).max(deviation(x2, y2)) <= tolerance {
+        out.push((x3, y3));
+        return;
+    }
+
+    let mid = |a: (f32, f32), b: (f32, f32)| ((a.0 + b.0) / 2.0, (a.1 + b.1) / 2.0);
+    let p01 = mid(p[0], p[1]);
+    let p12 = mid(p[1], p[2]);
+    let p23 = mid(p[2], p[3]);
+    let p012 = mid(p01, p12);
+    let p123 = mid(p12, p23);
+    let split = mid(p012, p123);
+
+    flatten_cubic([p[0], p01, p012, split], tolerance, depth + 1, out);
+    flatten_cubic([split, p123, p23, p[3]], tolerance, depth + 1, out);
+}
+
+fn add_cap_coverage(
+    coverage: &mut BTreeMap<(i32, i32), f32>,
+    end: Point,
+    outward_x: f32,
+    outward_y: f32,
+) {
+    const CAP_RADIUS: f32 = 1.0;
+    let reach = CAP_RADIUS as i32 + 1;
+
+    for y in (end.y - reach)..=(end.y + reach) {
+        for x in (end.x - reach)..=(end.x + reach) {
+            let dx = (x - end.x) as f32;
+            let dy = (y - end.y) as f32;
+            let behind = dx * outward_x + dy * outward_y <= 0.0;
+            if behind && (dx != 0.0 || dy != 0.0) {
+                continue;
+            }
+
+            let dist = sqrtf(dx * dx + dy * dy);
+            add_coverage(coverage, x, y, (CAP_RADIUS + 0.5 - dist).clamp(0.0, 1.0));
+        }
+    }
+}
+
+fn rounded_rect_sdf(px: f32, py: f32, rect: Rect, radius: f32) -> f32 {
+    let half_w = rect.width as f32 / 2.0;
+    let half_h = rect.height as f32 / 2.0;
+    let qx = (px - (rect.x as f32 + half_w)).abs() - (half_w - radius);
+    let qy = (py - (rect.y as f32 + half_h)).abs() - (half_h - radius);
+
+    let outside = sqrtf(qx.max(0.0) * qx.max(0.0) + qy.max(0.0) * qy.max(0.0));
+    let inside = qx.max(qy).min(0.0);
+    outside + inside - radius
+}
+
+fn ellipse_distance(dx: f32, dy: f32, rx: f32, ry: f32) -> f32 {
+    let rx_sq = rx * rx;
+    let ry_sq = ry * ry;
+    let f = dx * dx / rx_sq + dy * dy / ry_sq - 1.0;
+    let gx = 2.0 * dx / rx_sq;
+    let gy = 2.0 * dy / ry_sq;
+    let grad = sqrtf(gx * gx + gy * gy);
+
+    if grad == 0.0 {
+        -rx.min(ry)
+    } else {
+        f / grad
+    }
+}
+
+pub fn regular_polygon_points(center: Point, radius: i32, sides: u32, rotation: f32) -> Vec<Point> {
+    if sides < 3 {
+        return Vec::new();
+    }
+
+    let pi = core::f32::consts::PI;
+    let start = pi / 2.0 + pi / sides as f32 + rotation;
+    let step = 2.0 * pi / sides as f32;
+
+    (0..sides)
+        .map(|i| polar_point(center, radius as f32, start + step * i as f32))
+        .collect()
+}
+
+pub fn star_points(
+    center: Point,
+    outer_radius: i32,
+    inner_radius: i32,
+    points: u32,
+    rotation: f32,
+) -> Vec<Point> {
+    if points < 2 {
+        return Vec::new();
+    }
+
+    let pi = core::f32::consts::PI;
+    let start = -pi / 2.0 + rotation;
+    let step = pi / points as f32;
+
+    (0..points * 2)
+        .map(|i| {
+            let radius = if i % 2 == 0 {
+                outer_radius
+            } else {
+                inner_radius
+            };
+            polar_point(center, radius as f32, start + step * i as f32)
+        })
+        .collect()
+}
+
+fn rounded_polygon_points(points: &[Point], radius: i32) -> Vec<Point> {
+    let n = points.len();
+    if n < 3 || radius <= 0 {
+        return points.to_vec();
+    }
+
+    let mut outline = Vec::new();
+    for i in 0..n {
+        let vertex = points[i];
+        let prev = points[(i + n - 1) % n];
+        let next = points[(i + 1) % n];
+
+        let (ax, ay) = ((prev.x - vertex.x) as f32, (prev.y - vertex.y) as f32);
+        let (bx, by) = ((next.x - vertex.x) as f32, (next.y - vertex.y) as f32);
+        let len_a = sqrtf(ax * ax + ay * ay);
+        let len_b = sqrtf(bx * bx + by * by);
+        if len_a == 0.0 || len_b == 0.0 {
+            outline.push(vertex);
+            continue;
+        }
+
+        let (ux, uy) = (ax / len_a, ay / len_a);
+        let (vx, vy) = (bx / len_b, by / len_b);
+        let cos = (ux * vx + uy * vy).clamp(-1.0, 1.0);
+        let half_sin = sqrtf((1.0 - cos) / 2.0);
+        let half_cos = sqrtf((1.0 + cos) / 2.0);
+        if half_sin < 1e-3 || half_cos < 1e-3 {
+            outline.push(vertex);
+            continue;
+        }
+
+        let tangent = (radius as f32 * half_cos / half_sin).min(len_a.min(len_b) / 2.0);
+        let r = tangent * half_sin / half_cos;
+        let center_dist = r / half_sin;
+        let (bis_x, bis_y) = (ux + vx, uy + vy);
+        let bis_len = sqrtf(bis_x * bis_x + bis_y * bis_y);
+        let cx = vertex.x as f32 + bis_x / bis_len * center_dist;
+        let cy = vertex.y as f32 + bis_y / bis_len * center_dist;
+
+        let start_x = vertex.x as f32 + ux * tangent;
+        let start_y = vertex.y as f32 + uy * tangent;
+        let end_x = vertex.x as f32 + vx * tangent;
+        let end_y = vertex.y as f32 + vy * tangent;
+        let start = atan2f(start_y - cy, start_x - cx);
+        let mut sweep = atan2f(end_y - cy, end_x - cx) - start;
+        if sweep > core::f32::consts::PI {
+            sweep -= core::f32::consts::TAU;
+        } else if sweep < -core::f32::consts::PI {
+            sweep += core::f32::consts::TAU;
+        }
+
+        let steps = ((sweep.abs() * r) as usize).clamp(1, 64);
+        for step in 0..=steps {
+            let angle = start + sweep * step as f32 / steps as f32;
+            let point = Point::new(
+                (cx + cosf(angle) * r).round() as i32,
+                (cy + sinf(angle) * r).round() as i32,
+            );
+            if outline.last() != Some(&point) {
+                outline.push(point);
+            }
+        }
+    }
+
+    outline
+}
+
+fn nine_slice_source(offset: u32, dst_len: u32, src_len: u32, lead: u32, trail: u32) -> u32 {
+    if offset < lead {
+        return offset;
+    }
+    let from_end = dst_len - offset;
+    if from_end <= trail {
+        return src_len - from_end;
+    }
+
+    let src_middle = (src_len - lead - trail) as u64;
+    let dst_middle = dst_len.saturating_sub(lead + trail).max(1) as u64;
+    lead + ((offset - lead) as u64 * src_middle / dst_middle) as u32
+}
+
+fn polar_point(center: Point, radius: f32, angle: f32) -> Point {
+    Point::new(
+        center.x + (cosf(angle) * radius).round() as i32,
+        center.y + (sinf(angle) * radius).round() as i32,
+    )
+}
+
+fn linear_channel(value: u8) -> f32 {
+    let c = value as f32 / 255.0;
+    if c <= 0.03928 {
+        c / 12.92
+    } else {
+        powf((c + 0.055) / 1.055, 2.4)
+    }
+}
+
+pub fn caret_visible(elapsed_ms: u64, period_ms: u64) -> bool {
+    if period_ms == 0 {
+        return true;
+    }
+    elapsed_ms % period_ms < period_ms / 2
+}
+
+fn average_fps(frame_times: &[f32]) -> f32 {
+    let total_ms: f32 = frame_times.iter().sum();
+    if total_ms <= 0.0 {
+        return 0.0;
+    }
+    frame_times.len() as f32 * 1000.0 / total_ms
+}
+
+pub fn isqrt(n: u32) -> u32 {
+    let mut op = n;
+    let mut res = 0;
+    let mut one = 1u32 << 30;
+
+    while one > op {
+        one >>= 2;
+    }
+
+    while one != 0 {
+        if op >= res + one {
+            op -= res + one;
+            res = (res >> 1) + one;
+        } else {
+            res >>= 1;
+        }
+        one >>= 2;
+    }
+
+    res
+}
+
+// With std linked in tests the inherent float methods shadow these.
+#[cfg_attr(test, allow(dead_code))]
+trait FloatExt {
+    fn fpart(self) -> Self;
+    fn floor(self) -> Self;
+    fn ceil(self) -> Self;
+    fn round(self) -> Self;
+}
+
+impl FloatExt for f32 {
+    fn fpart(self) -> f32 {
+        self - self.floor()
+    }
+
+    fn floor(self) -> f32 {
+        libm::floorf(self)
+    }
+
+    fn ceil(self) -> f32 {
+        libm::ceilf(self)
+    }
+
+    fn round(self) -> f32 {
+        libm::roundf(self)
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use core::cell::Cell;
+
+    std::thread_local! {
+        pub(crate) static FLUSHES: Cell<usize> = const { Cell::new(0) };
+    }
+
+    pub(crate) fn px(sight: &Sight, x: i32, y: i32) -> Color {
+        sight.get_pixel(x, y).unwrap()
+    }
+
+    #[test]
+    fn isqrt_matches_float_sqrt() {
+        for n in 0..70_000 {
+            assert_eq!(isqrt(n), sqrtf(n as f32) as u32, "isqrt({n})");
+        }
+        for n in [1 << 24, 1 << 31, u32::MAX - 1, u32::MAX] {
+            let root = isqrt(n) as u64;
+            assert!(root * root <= n as u64 && (root + 1) * (root + 1) > n as u64);
+        }
+    }
+
+    #[test]
+    fn alpha_helpers() {
+        let c = Color::rgba(10, 20, 30, 200);
+        assert_eq!(c.with_alpha(7), Color::rgba(10, 20, 30, 7));
+        assert_eq!(c.scale_alpha(0.5), Color::rgba(10, 20, 30, 100));
+        assert_eq!(c.scale_alpha(2.0), c);
+        assert_eq!(c.scale_alpha(-1.0).a, 0);
+    }
+
+    #[test]
+    fn fade_region_half_toward_black() {
+        let mut sight = Sight::headless(4, 4);
+        sight.clear(Color::rgb(200, 100, 50));
+        sight.fade_region(Rect::new(0, 0, 2, 4), Color::BLACK, 0.5);
+        assert_eq!(px(&sight, 1, 3), Color::rgb(100, 50, 25));
+        assert_eq!(px(&sight, 2, 0), Color::rgb(200, 100, 50));
+    }
+
+    #[test]
+    fn triangle_aa_blends_vertices_once() {
+        let mut sight = Sight::headless(16, 16);
+        sight.clear(Color::BLACK);
+        let color = Color::WHITE.with_alpha(128);
+        sight.draw_triangle_aa(
+            Point::new(2, 2),
+            Point::new(12, 2),
+            Point::new(2, 12),
+            color,
+        );
+
+        let edge = px(&sight, 7, 2);
+        assert_eq!(edge, color.blend(Color::BLACK));
+        for (x, y) in [(2, 2), (12, 2), (2, 12), (2, 7), (7, 7)] {
+            assert_eq!(px(&sight, x, y), edge, "({x}, {y})");
+        }
+    }
+
+    #[test]
+    fn round_caps_extend_past_endpoints() {
+        let mut sight = Sight::headless(16, 10);
+        sight.clear(Color::BLACK);
+        sight.draw_line_capped(Point::new(3, 5), Point::new(10, 5), Color::WHITE, false);
+        assert_eq!(px(&sight, 11, 5), Color::BLACK);
+
+        sight.clear(Color::BLACK);
+        sight.draw_line_capped(Point::new(3, 5), Point::new(10, 5), Color::WHITE, true);
+        assert_eq!(px(&sight, 10, 5), Color::WHITE);
+        for (x, y) in [(11, 5), (2, 5), (11, 6), (2, 4)] {
+            let r = px(&sight, x, y).r;
+            assert!(r > 0 && r < 255, "({x}, {y}) = {r}");
+        }
+        assert_eq!(px(&sight, 12, 5), Color::BLACK);
+    }
+
+    #[test]
+    fn present_skips_clean_frames() {
+        let mut sight = Sight::headless(4, 4);
+        let flushes = || FLUSHES.with(Cell::get);
+        let before = flushes();
+
+        sight.put_pixel(1, 1, Color::RED);
+        assert!(sight.needs_present());
+        sight.present().unwrap();
+        sight.present().unwrap();
+        assert_eq!(flushes() - before, 1);
+        assert!(!sight.needs_present());
+    }
+
+    #[test]
+    fn present_rejects_unmapped_framebuffer() {
+        let mut sight = Sight::headless(4, 4);
+        sight.put_pixel(0, 0, Color::RED);
+        sight.fb = core::ptr::null_mut();
+        assert_eq!(sight.present(), Err("Framebuffer not mapped"));
+        assert_eq!(sight.force_present(), Err("Framebuffer not mapped"));
+        sight.fb = alloc::vec![0u32; 16].leak().as_mut_ptr();
+        sight.width = 0;
+        assert_eq!(sight.present(), Err("Invalid framebuffer size"));
+    }
+
+    #[test]
+    fn linear_gradient_follows_direction() {
+        let mut sight = Sight::headless(12, 12);
+        let (start, end) = (Point::new(2, 2), Point::new(9, 9));
+        sight.fill_gradient_linear(Rect::new(0, 0, 12, 12), start, end, Color::RED, Color::BLUE);
+
+        assert_eq!(px(&sight, 2, 2), Color::RED);
+        assert_eq!(px(&sight, 9, 9), Color::BLUE);
+        for offset in 1..5 {
+            assert_eq!(px(&sight, 5 + offset, 5 - offset), px(&sight, 5, 5));
+            assert_eq!(px(&sight, 5 - offset, 5 + offset), px(&sight, 5, 5));
+        }
+    }
+
+    #[test]
+    fn stencil_limits_drawing_to_circle() {
+        let mut sight = Sight::headless(12, 12);
+        let inside = |x: i32, y: i32| (x - 6) * (x - 6) + (y - 6) * (y - 6) <= 16;
+        sight.set_stencil_from(inside);
+        sight.set_stencil_mode(StencilMode::Inside);
+        sight.fill_rect(Rect::new(0, 0, 12, 12), Color::RED);
+        sight.draw_line(Point::new(0, 6), Point::new(11, 6), Color::BLUE);
+
+        for y in 0..12 {
+            for x in 0..12 {
+                let written = px(&sight, x, y) != Color::TRANSPARENT;
+                assert_eq!(written, inside(x, y), "({x}, {y})");
+            }
+        }
+
+        sight.set_stencil_mode(StencilMode::Outside);
+        sight.put_pixel(6, 6, Color::GREEN);
+        sight.put_pixel(0, 0, Color::GREEN);
+        assert_eq!(px(&sight, 6, 6), Color::BLUE);
+        assert_eq!(px(&sight, 0, 0), Color::GREEN);
+    }
+
+    #[test]
+    fn arc_has_no_angular_gaps() {
+        let mut sight = Sight::headless(220, 220);
+        let center = Point::new(110, 110);
+        sight.draw_arc(center, 100, 0.0, core::f32::consts::PI, Color::WHITE);
+
+        let mut covered = [false; 150];
+        for y in 0..220 {
+            for x in 0..220 {
+                if px(&sight, x, y) == Color::TRANSPARENT {
+                    continue;
+                }
+                let angle = atan2f((y - center.y) as f32, (x - center.x) as f32);
+                let span = (angle / core::f32::consts::PI * covered.len() as f32) as usize;
+                covered[span.min(covered.len() - 1)] = true;
+            }
+        }
+        assert!(covered.iter().all(|&c| c));
+    }
+
+    #[test]
+    fn pattern_tiles_across_rect() {
+        let tile = [Color::RED, Color::GREEN, Color::BLUE, Color::WHITE];
+        let mut pattern = bmp::BmpImage::new(2, 2);
+        for (i, &color) in tile.iter().enumerate() {
+            pattern.set_pixel(i as u32 % 2, i as u32 / 2, color);
+        }
+
+        let mut sight = Sight::headless(6, 6);
+        sight.fill_rect_pattern(Rect::new(1, 1, 4, 4), &pattern, Point::new(0, 0));
+        for y in 0..4 {
+            for x in 0..4 {
+                assert_eq!(px(&sight, x + 1, y + 1), tile[(y % 2 * 2 + x % 2) as usize]);
+            }
+        }
+        assert_eq!(px(&sight, 0, 0), Color::TRANSPARENT);
+        assert_eq!(px(&sight, 5, 5), Color::TRANSPARENT);
+
+        sight.fill_rect_pattern(Rect::new(1, 1, 4, 4), &pattern, Point::new(1, 0));
+        assert_eq!(px(&sight, 1, 1), Color::GREEN);
+    }
+
+    #[test]
+    fn rounded_border_has_thickness() {
+        let mut sight = Sight::headless(40, 30);
+        sight.clear(Color::BLACK);
+        sight.draw_rounded_rect_thick(Rect::new(5, 5, 30, 20), 6, 3, Color::WHITE);
+
+        let row: Vec<u8> = (0..40).map(|x| px(&sight, x, 15).r).collect();
+        let lit: Vec<usize> = (0..40).filter(|&x| row[x] == 255).collect();
+        assert_eq!(lit, [5, 6, 7, 32, 33, 34]);
+        assert!(row.iter().all(|&r| r == 0 || r == 255));
+
+        let corner = (5..12).flat_map(|x| (5..12).map(move |y| (x, y)));
+        assert!(corner
+            .map(|(x, y)| px(&sight, x, y).r)
+            .any(|r| r > 0 && r < 255));
+    }
+
+    #[test]
+    fn fast_fills_match_scalar() {
+        let color = Color::rgb(12, 34, 56);
+        let rect = Rect::new(-2, 3, 7, 9);
+        for y_up in [false, true] {
+            let mut fast = Sight::headless(9, 8);
+            let mut scalar = Sight::headless(9, 8);
+            fast.set_y_up(y_up);
+            scalar.set_y_up(y_up);
+
+            fast.clear(Color::WHITE);
+            fast.fill_rect(rect, color);
+            for y in 0..8 {
+                for x in 0..9 {
+                    let inside = rect.contains(Point::new(x, y));
+                    scalar.put_pixel(x, y, if inside { color } else { Color::WHITE });
+                }
+            }
+            assert_eq!(fast.buffer(), scalar.buffer());
+        }
+    }
+
+    #[test]
+    fn y_up_draws_from_bottom_row() {
+        let mut sight = Sight::headless(4, 4);
+        sight.set_y_up(true);
+        sight.put_pixel(1, 0, Color::RED);
+        assert_eq!(px(&sight, 1, 3), Color::RED);
+
+        sight.fill_rect(Rect::new(0, 0, 4, 1), Color::BLUE);
+        assert_eq!(px(&sight, 0, 3), Color::BLUE);
+        assert_eq!(px(&sight, 0, 0), Color::TRANSPARENT);
+    }
+
+    #[test]
+    fn rect_points_are_row_major() {
+        let points: Vec<Point> = Rect::new(4, -1, 2, 3).points().collect();
+        let expected = [(4, -1), (5, -1), (4, 0), (5, 0), (4, 1), (5, 1)];
+        assert_eq!(points, expected.map(|(x, y)| Point::new(x, y)));
+        assert_eq!(Rect::new(0, 0, 0, 3).points().count(), 0);
+    }
+
+    #[test]
+    fn antialias_toggle_controls_line_edges() {
+        let partial = |sight: &Sight| {
+            (0..16)
+                .flat_map(|y| (0..16).map(move |x| (x, y)))
+                .filter(|&(x, y)| !matches!(px(sight, x, y).r, 0 | 255))
+                .count()
+        };
+
+        let mut sight = Sight::headless(16, 16);
+        sight.clear(Color::BLACK);
+        sight.draw_line(Point::new(1, 2), Point::new(14, 9), Color::WHITE);
+        assert!(partial(&sight) > 0);
+
+        sight.clear(Color::BLACK);
+        sight.set_antialias(false);
+        sight.draw_line(Point::new(1, 2), Point::new(14, 9), Color::WHITE);
+        assert_eq!(partial(&sight), 0);
+        for x in 1..=14 {
+            let lit = (0..16)
+                .filter(|&y| px(&sight, x, y) == Color::WHITE)
+                .count();
+            assert_eq!(lit, 1, "column {x}");
+        }
+    }
+
+    #[test]
+    fn crosshair_and_marker_arms() {
+        let mut sight = Sight::headless(9, 9);
+        sight.draw_crosshair(Point::new(4, 4), 3, Color::WHITE);
+        for y in 0..9 {
+            for x in 0..9 {
+                let arm = (y == 4 && (1..=7).contains(&x)) || (x == 4 && (1..=7).contains(&y));
+                assert_eq!(px(&sight, x, y) == Color::WHITE, arm, "({x}, {y})");
+            }
+        }
+
+        let mut sight = Sight::headless(9, 9);
+        sight.draw_x_marker(Point::new(1, 1), 3, Color::WHITE);
+        assert_eq!(px(&sight, 4, 4), Color::WHITE);
+        assert_eq!(px(&sight, 0, 2), Color::WHITE);
+        assert_eq!(px(&sight, 1, 2), Color::TRANSPARENT);
+    }
+
+    #[test]
+    fn aa_ellipse_and_arc_are_smooth_and_closed() {
+        let mut sight = Sight::headless(40, 24);
+        sight.clear(Color::BLACK);
+        sight.draw_ellipse_aa(Point::new(20, 12), 15, 8, Color::WHITE);
+
+        let lit = |sight: &Sight, x: i32, y: i32| px(sight, x, y).r > 0;
+        assert!((0..40).any(|x| (0..24).any(|y| !matches!(px(&sight, x, y).r, 0 | 255))));
+        for x in 6..=34 {
+            assert!((0..12).any(|y| lit(&sight, x, y)), "top gap at {x}");
+            assert!((13..24).any(|y| lit(&sight, x, y)), "bottom gap at {x}");
+        }
+        for y in 5..=19 {
+            assert!((0..20).any(|x| lit(&sight, x, y)), "left gap at {y}");
+            assert!((21..40).any(|x| lit(&sight, x, y)), "right gap at {y}");
+        }
+
+        sight.clear(Color::BLACK);
+        sight.draw_arc_aa(
+            Point::new(20, 12),
+            10,
+            0.0,
+            core::f32::consts::FRAC_PI_2,
+            Color::WHITE,
+        );
+        assert!(lit(&sight, 30, 12) && lit(&sight, 20, 22));
+        for x in 20..=30 {
+            assert!((12..=22).any(|y| lit(&sight, x, y)), "arc gap at {x}");
+        }
+        assert!((20..=30).any(|x| (12..=22).any(|y| !matches!(px(&sight, x, y).r, 0 | 255))));
+        assert!(!(0..40).any(|x| (0..12).any(|y| lit(&sight, x, y))));
+    }
+
+    #[test]
+    fn scroll_shifts_content_and_fills_edges() {
+        let mut sight = Sight::headless(5, 4);
+        sight.clear(Color::BLACK);
+        sight.put_pixel(1, 1, Color::RED);
+        sight.scroll(2, 1, Color::BLUE);
+        assert_eq!(px(&sight, 3, 2), Color::RED);
+        assert_eq!(px(&sight, 1, 1), Color::BLUE);
+        assert_eq!(px(&sight, 4, 0), Color::BLUE);
+        assert_eq!(px(&sight, 4, 3), Color::BLACK);
+
+        sight.scroll(-3, -2, Color::GREEN);
+        assert_eq!(px(&sight, 0, 0), Color::RED);
+        assert_eq!(px(&sight, 4, 0), Color::GREEN);
+        assert_eq!(px(&sight, 0, 3), Color::GREEN);
+    }
+
+    #[test]
+    fn mix_and_average() {
+        assert_eq!(
+            Color::mix(&[(Color::RED, 1.0), (Color::BLUE, 1.0)]),
+            Color::rgb(128, 0, 128)
+        );
+        assert_eq!(
+            Color::mix(&[(Color::RED, 3.0), (Color::BLACK, 1.0)]),
+            Color::rgb(191, 0, 0)
+        );
+        assert_eq!(Color::mix(&[(Color::RED, 0.0)]), Color::TRANSPARENT);
+        assert_eq!(
+            Color::average(&[Color::RED, Color::GREEN, Color::BLUE]),
+            Color::rgb(85, 85, 85)
+        );
+        assert_eq!(Color::average(&[]), Color::TRANSPARENT);
+    }
+
+    #[test]
+    fn bevel_edges_use_light_and_dark() {
+        let (light, dark) = (Color::WHITE, Color::rgb(60, 60, 60));
+        let mut sight = Sight::headless(8, 6);
+        sight.draw_bevel(Rect::new(1, 1, 6, 4), light, dark, true);
+        assert_eq!(px(&sight, 3, 1), light);
+        assert_eq!(px(&sight, 1, 3), light);
+        assert_eq!(px(&sight, 3, 4), dark);
+        assert_eq!(px(&sight, 6, 2), dark);
+
+        sight.draw_bevel(Rect::new(1, 1, 6, 4), light, dark, false);
+        assert_eq!(px(&sight, 3, 1), dark);
+        assert_eq!(px(&sight, 3, 4), light);
+    }
+
+    #[test]
+    fn subpixel_disk_moves_mass_not_total() {
+        let stamp = |cx: f32| {
+            let mut sight = Sight::headless(20, 20);
+            sight.clear(Color::BLACK);
+            sight.fill_disk_subpixel(cx, 10.0, 3.0, Color::WHITE);
+            let (mut mass, mut moment) = (0.0, 0.0);
+            for y in 0..20 {
+                for x in 0..20 {
+                    let coverage = px(&sight, x, y).r as f32 / 255.0;
+                    mass += coverage;
+                    moment += coverage * x as f32;
+                }
+            }
+            (mass, moment / mass)
+        };
+
+        let (mass, centroid) = stamp(10.0);
+        let (shifted_mass, shifted_centroid) = stamp(10.5);
+        assert!((centroid - 10.0).abs() < 0.05);
+        assert!((shifted_centroid - 10.5).abs() < 0.1);
+        assert!((shifted_mass - mass).abs() / mass < 0.05);
+    }
+
+    #[test]
+    fn clear_variants() {
+        let mut sight = Sight::headless(4, 4);
+        sight.clear(Color::WHITE);
+        sight.clear_transparent();
+        assert!(sight.buffer().iter().all(|&p| Color::from_u32(p).a == 0));
+
+        sight.clear_checker(2, Color::RED, Color::BLUE);
+        assert_eq!(px(&sight, 1, 1), Color::RED);
+        assert_eq!(px(&sight, 2, 1), Color::BLUE);
+        assert_eq!(px(&sight, 2, 2), Color::RED);
+    }
+
+    #[test]
+    fn transparent_canvas_keeps_alpha() {
+        let mut sight = Sight::headless(2, 2);
+        sight.clear_transparent();
+        sight.blend_pixel(0, 0, Color::rgba(255, 0, 0, 128));
+        assert_eq!(px(&sight, 0, 0), Color::rgba(255, 0, 0, 128));
+
+        sight.blend_pixel(0, 0, Color::rgba(0, 0, 255, 128));
+        let stacked = px(&sight, 0, 0);
+        assert!(stacked.a > 128 && stacked.a < 255);
+        assert!(stacked.b > stacked.r);
+    }
+
+    #[test]
+    fn marching_ants_shift_with_phase() {
+        let ants = |phase| {
+            let mut sight = Sight::headless(10, 6);
+            sight.draw_marching_ants(Rect::new(0, 0, 10, 6), phase, Color::WHITE, Color::BLACK);
+            sight
+        };
+
+        let still = ants(0);
+        let top: Vec<Color> = (0..8).map(|x| px(&still, x, 0)).collect();
+        assert_eq!(top[..4], [Color::WHITE; 4]);
+        assert_eq!(top[4..], [Color::BLACK; 4]);
+
+        let moved = ants(1);
+        assert_eq!(px(&moved, 2, 0), Color::WHITE);
+        assert_eq!(px(&moved, 3, 0), Color::BLACK);
+        assert_eq!(px(&moved, 7, 0), Color::WHITE);
+        assert_eq!(ants(8).buffer(), still.buffer());
+        assert_eq!(px(&still, 4, 3), Color::TRANSPARENT);
+    }
+
+    #[test]
+    fn point_rotation() {
+        use core::f32::consts::{FRAC_PI_2, PI};
+
+        assert_eq!(Point::new(3, 0).rotate(FRAC_PI_2), Point::new(0, 3));
+        assert_eq!(Point::new(3, 1).rotate(PI), Point::new(-3, -1));
+        let center = Point::new(10, 5);
+        assert_eq!(
+            Point::new(12, 5).rotate_around(center, FRAC_PI_2),
+            Point::new(10, 7)
+        );
+        assert_eq!(
+            Point::new(12, 6).rotate_around(center, PI),
+            Point::new(8, 4)
+        );
+        assert_eq!(center.rotate_around(center, 1.0), center);
+    }
+
+    #[test]
+    fn polygon_and_star_generators() {
+        let diamond =
+            regular_polygon_points(Point::new(10, 10), 5, 4, core::f32::consts::FRAC_PI_4);
+        let expected = [(5, 10), (10, 5), (15, 10), (10, 15)].map(|(x, y)| Point::new(x, y));
+        assert_eq!(diamond, expected);
+        assert!(regular_polygon_points(Point::new(0, 0), 5, 2, 0.0).is_empty());
+
+        let star = star_points(Point::new(0, 0), 10, 4, 5, 0.0);
+        assert_eq!(star.len(), 10);
+        assert_eq!(star[0], Point::new(0, -10));
+        assert!(star_points(Point::new(0, 0), 10, 4, 1, 0.0).is_empty());
+    }
+
+    #[test]
+    fn frame_stats_average_fps() {
+        let mut sight = Sight::headless(4, 4);
+        assert_eq!(sight.stats().average_fps, 0.0);
+
+        for frame_ms in [10.0, 20.0, 30.0] {
+            sight.record_frame_time(frame_ms);
+        }
+        let stats = sight.stats();
+        assert_eq!(stats.last_frame_ms, 30.0);
+        assert!((stats.average_fps - 50.0).abs() < 1e-3);
+
+        for _ in 0..FRAME_HISTORY {
+            sight.record_frame_time(25.0);
+        }
+        assert!((sight.stats().average_fps - 40.0).abs() < 1e-3);
+
+        sight.fill_rect(Rect::new(0, 0, 2, 2), Color::RED);
+        sight.present().unwrap();
+        assert_eq!(sight.stats().pixels_written, 4);
+    }
+
+    #[test]
+    fn circle_gradient_runs_from_center_to_rim() {
+        let mut sight = Sight::headless(21, 21);
+        sight.clear(Color::GREEN);
+        let center = Point::new(10, 10);
+        sight.fill_circle_gradient(center, 8, Color::WHITE, Color::BLACK);
+
+        assert_eq!(px(&sight, 10, 10), Color::WHITE);
+        assert!(px(&sight, 18, 10).r < 10);
+        assert!(px(&sight, 10, 2).r < 10);
+        assert!(px(&sight, 14, 10).r < px(&sight, 12, 10).r);
+        for y in 0..21 {
+            for x in 0..21 {
+                if Point::new(x, y).distance_to(center) > 8.5 {
+                    assert_eq!(px(&sight, x, y), Color::GREEN, "({x}, {y})");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn line_gradient_blends_along_length() {
+        let mut sight = Sight::headless(12, 3);
+        sight.draw_line_gradient(Point::new(0, 1), Point::new(10, 1), Color::RED, Color::BLUE);
+
+        assert_eq!(px(&sight, 0, 1), Color::RED);
+        assert_eq!(px(&sight, 10, 1), Color::BLUE);
+        let mid = px(&sight, 5, 1);
+        let expected = Color::RED.lerp(Color::BLUE, 0.5);
+        assert!(mid.r.abs_diff(expected.r) <= 2 && mid.b.abs_diff(expected.b) <= 2);
+    }
+
+    #[test]
+    fn flood_fill_tolerance_covers_soft_blob() {
+        let blob = |sight: &mut Sight| {
+            sight.clear(Color::BLACK);
+            for y in 1..8 {
+                for x in 1..8 {
+                    let ring = (x - 4i32).abs().max((y - 4i32).abs()) as u8;
+                    let v = 255 - ring * 10;
+                    sight.put_pixel(x, y, Color::rgb(v, v, v));
+                }
+            }
+        };
+        let filled = |sight: &Sight| {
+            sight
+                .buffer()
+                .iter()
+                .filter(|&&p| p == Color::RED.to_u32())
+                .count()
+        };
+
+        let mut sight = Sight::headless(9, 9);
+        blob(&mut sight);
+        sight.flood_fill(4, 4, Color::RED);
+        assert_eq!(filled(&sight), 1);
+
+        blob(&mut sight);
+        sight.flood_fill_tolerance(4, 4, Color::RED, 60.0);
+        assert_eq!(filled(&sight), 49);
+        assert_eq!(px(&sight, 0, 0), Color::BLACK);
+    }
+
+    #[test]
+    fn rounded_bmp_skips_corners() {
+        let mut image = bmp::BmpImage::new(2, 2);
+        for (x, y) in [(0, 0), (1, 0), (0, 1), (1, 1)] {
+            image.set_pixel(x, y, Color::RED);
+        }
+
+        let mut sight = Sight::headless(14, 14);
+        sight.draw_bmp_rounded(&image, Rect::new(2, 2, 10, 10), 4);
+        for (x, y) in [(2, 2), (11, 2), (2, 11), (11, 11)] {
+            assert_eq!(px(&sight, x, y), Color::TRANSPARENT, "({x}, {y})");
+        }
+        for (x, y) in [(7, 7), (7, 2), (2, 7), (11, 7)] {
+            assert_eq!(px(&sight, x, y), Color::RED, "({x}, {y})");
+        }
+        assert_eq!(px(&sight, 1, 7), Color::TRANSPARENT);
+    }
+
+    #[test]
+    fn caret_blinks_and_draws_span() {
+        assert!(caret_visible(0, 1000));
+        assert!(caret_visible(499, 1000));
+        assert!(!caret_visible(500, 1000));
+        assert!(caret_visible(1200, 1000));
+        assert!(caret_visible(123, 0));
+
+        let mut sight = Sight::headless(4, 8);
+        sight.draw_caret(2, 1, 5, Color::WHITE);
+        let lit: Vec<i32> = (0..8)
+            .filter(|&y| px(&sight, 2, y) == Color::WHITE)
+            .collect();
+        assert_eq!(lit, [1, 2, 3, 4, 5]);
+        assert_eq!(px(&sight, 1, 3), Color::TRANSPARENT);
+    }
+
+    #[test]
+    fn aa_polygon_fill_has_soft_edges() {
+        let mut sight = Sight::headless(24, 24);
+        sight.clear(Color::BLACK);
+        let circle = regular_polygon_points(Point::new(12, 12), 9, 32, 0.0);
+        sight.fill_polygon_aa(&circle, Color::WHITE);
+
+        for (x, y) in [(12, 12), (8, 12), (12, 16), (15, 9)] {
+            assert_eq!(px(&sight, x, y), Color::WHITE);
+        }
+        let values: Vec<u8> = (0..24)
+            .flat_map(|y| (0..24).map(move |x| (x, y)))
+            .map(|(x, y)| px(&sight, x, y).r)
+            .collect();
+        assert!(values.iter().any(|&r| r > 0 && r < 255));
+        assert_eq!(px(&sight, 1, 1), Color::BLACK);
+        assert_eq!(px(&sight, 22, 12), Color::BLACK);
+    }
+
+    #[test]
+    fn pixel_formats_order_channels() {
+        let c = Color::rgba(0x11, 0x22, 0x33, 0x44);
+        let layouts = [
+            (PixelFormat::Argb, 0x4411_2233),
+            (PixelFormat::Rgba, 0x1122_3344),
+            (PixelFormat::Abgr, 0x4433_2211),
+            (PixelFormat::Bgra, 0x3322_1144),
+        ];
+        for (format, value) in layouts {
+            assert_eq!(c.to_format(format), value);
+            assert_eq!(Color::from_format(value, format), c);
+        }
+        assert_eq!(c.to_u32(), 0x4411_2233);
+
+        let mut sight = Sight::headless(2, 2);
+        sight.put_pixel(0, 0, c);
+        sight.set_pixel_format(PixelFormat::Rgba);
+        assert_eq!(sight.buffer()[0], 0x4411_2233);
+        assert_eq!(sight.get_pixel(0, 0), Some(c));
+        assert_eq!(sight.export_buffer()[0], 0x1122_3344);
+    }
+
+    #[test]
+    fn focus_ring_surrounds_rect() {
+        let mut sight = Sight::headless(40, 30);
+        sight.clear(Color::BLACK);
+        let rect = Rect::new(10, 10, 20, 10);
+        sight.draw_focus_ring(rect, 3, 2, Color::WHITE);
+
+        let mut partial = false;
+        for y in 0..30 {
+            for x in 0..40 {
+                let r = px(&sight, x, y).r;
+                if rect.contains(Point::new(x, y)) {
+                    assert_eq!(r, 0, "({x}, {y})");
+                }
+                partial |= r > 0 && r < 255;
+            }
+        }
+        assert!(partial);
+        assert_eq!(px(&sight, 20, 6), Color::WHITE);
+        assert_eq!(px(&sight, 20, 8), Color::BLACK);
+    }
+
+    #[test]
+    fn blend_pixel_matches_color_blend() {
+        let background = Color::rgb(10, 200, 30);
+        let color = Color::rgba(200, 20, 100, 100);
+        let mut sight = Sight::headless(2, 2);
+        sight.clear(background);
+        sight.blend_pixel(1, 1, color);
+        assert_eq!(px(&sight, 1, 1), color.blend(background));
+        assert_eq!(px(&sight, 0, 0), background);
+        sight.blend_pixel(-1, 5, color);
+    }
+
+    #[test]
+    fn crt_upscale_darkens_odd_rows() {
+        let color = Color::rgb(200, 100, 50);
+        let mut sight = Sight::headless(4, 4);
+        sight.put_pixel(0, 0, color);
+        sight.put_pixel(1, 0, Color::WHITE);
+        sight.put_pixel(0, 1, Color::BLUE);
+        sight.present_crt(2, 0.5).unwrap();
+
+        for (x, y) in [(0, 0), (1, 0)] {
+            assert_eq!(px(&sight, x, y), color);
+            assert_eq!(px(&sight, x, y + 1), Color::rgb(100, 50, 25));
+        }
+        assert_eq!(px(&sight, 2, 0), Color::WHITE);
+        assert_eq!(px(&sight, 0, 2), Color::BLUE);
+        assert_eq!(px(&sight, 0, 3), Color::rgb(0, 0, 127));
+    }
+
+    #[test]
+    fn crt_rejects_invalid_factors() {
+        let mut sight = Sight::headless(4, 4);
+        let flushes = || FLUSHES.with(Cell::get);
+        let before = flushes();
+
+        sight.put_pixel(0, 0, Color::WHITE);
+        assert_eq!(sight.present_crt(0, 0.5), Err("Invalid CRT factor"));
+        assert_eq!(sight.present_crt(5, 0.5), Err("Invalid CRT factor"));
+        assert_eq!(flushes(), before);
+        assert!(sight.needs_present());
+
+        sight.present_crt(4, 0.5).unwrap();
+        assert_eq!(flushes() - before, 1);
+    }
+
+    #[test]
+    fn crt_upscale_is_stable_and_honours_y_up() {
+        let mut sight = Sight::headless(4, 4);
+        sight.set_y_up(true);
+        sight.put_pixel(0, 0, Color::WHITE);
+        sight.present_crt(2, 0.5).unwrap();
+        let first = sight.buffer().to_vec();
+        sight.present_crt(2, 0.5).unwrap();
+        assert_eq!(sight.buffer(), &first[..]);
+
+        assert_eq!(px(&sight, 0, 3), Color::WHITE);
+        assert_eq!(px(&sight, 0, 2), Color::rgb(127, 127, 127));
+        assert_eq!(px(&sight, 2, 3), Color::TRANSPARENT);
+    }
+
+    #[test]
+    fn gradient_samples_sorted_stops() {
+        let gradient = Gradient::new(&[(1.0, Color::RED), (0.0, Color::BLUE), (0.5, Color::GREEN)]);
+        assert_eq!(gradient.stops()[1], (0.5, Color::GREEN));
+        assert_eq!(gradient.sample(-1.0), Color::BLUE);
+        assert_eq!(gradient.sample(0.5), Color::GREEN);
+        assert_eq!(gradient.sample(0.75), Color::GREEN.lerp(Color::RED, 0.5));
+        assert_eq!(gradient.sample(2.0), Color::RED);
+        assert_eq!(Gradient::new(&[]).sample(0.5), Color::TRANSPARENT);
+    }
+
+    #[test]
+    fn gradient_map_follows_luminance() {
+        let gradient = Gradient::new(&[(0.0, Color::BLUE), (0.5, Color::GREEN), (1.0, Color::RED)]);
+        let mut sight = Sight::headless(3, 1);
+        for (x, v) in [(0, 0), (1, 128), (2, 255)] {
+            sight.put_pixel(x, 0, Color::rgb(v, v, v));
+        }
+        sight.gradient_map(Rect::new(0, 0, 3, 1), &gradient);
+
+        assert_eq!(px(&sight, 0, 0), Color::BLUE);
+        assert!(px(&sight, 1, 0).g > 250);
+        assert_eq!(px(&sight, 2, 0), Color::RED);
+    }
+
+    #[test]
+    fn buffer_mut_writes_are_visible() {
+        let mut sight = Sight::headless(3, 2);
+        assert_eq!(sight.buffer().len(), 6);
+        assert!(!sight.needs_present());
+
+        sight.buffer_mut()[4] = Color::RED.to_u32();
+        assert_eq!(sight.get_pixel(1, 1), Some(Color::RED));
+        assert!(sight.needs_present());
+
+        sight.present().unwrap();
+        assert_eq!(sight.stats().pixels_written, 6);
+    }
+
+    #[test]
+    fn polygon_stroke_is_smooth_and_single_blended() {
+        let mut sight = Sight::headless(24, 18);
+        sight.clear(Color::BLACK);
+        let color = Color::WHITE.with_alpha(128);
+        let square = [(12, 2), (20, 6), (16, 14), (8, 10)].map(|(x, y)| Point::new(x, y));
+        sight.draw_polygon_aa(&square, color);
+
+        let full = color.blend(Color::BLACK);
+        assert_eq!(px(&sight, 14, 3), full);
+        for p in square {
+            assert_eq!(px(&sight, p.x, p.y), full, "{p:?}");
+        }
+        let r = px(&sight, 13, 2).r;
+        assert!(r > 0 && r < full.r);
+        assert!(sight
+            .buffer()
+            .iter()
+            .all(|&p| Color::from_u32(p).r <= full.r));
+    }
+
+    #[test]
+    fn fill_rects_matches_individual_fills() {
+        let rects = [
+            (Rect::new(0, 0, 4, 3), Color::RED),
+            (Rect::new(2, 1, 5, 5), Color::GREEN.with_alpha(100)),
+            (Rect::new(-3, 5, 20, 2), Color::BLUE),
+        ];
+        let mut batched = Sight::headless(8, 8);
+        let mut single = Sight::headless(8, 8);
+        batched.fill_rects(&rects);
+        for (rect, color) in rects {
+            single.fill_rect(rect, color);
+        }
+        assert_eq!(batched.buffer(), single.buffer());
+        assert!(batched.needs_present());
+    }
+
+    #[test]
+    fn aa_ellipse_fill_has_soft_extremes() {
+        let mut sight = Sight::headless(30, 16);
+        sight.clear(Color::BLACK);
+        sight.fill_ellipse_aa(Point::new(15, 8), 12, 6, Color::WHITE);
+
+        assert_eq!(px(&sight, 15, 8), Color::WHITE);
+        assert_eq!(px(&sight, 20, 10), Color::WHITE);
+        for (x, y) in [(27, 8), (3, 8), (15, 14), (15, 2)] {
+            let r = px(&sight, x, y).r;
+            assert!(r > 0 && r < 255, "({x}, {y}) = {r}");
+        }
+        assert_eq!(px(&sight, 28, 8), Color::BLACK);
+        assert_eq!(px(&sight, 15, 15), Color::BLACK);
+    }
+
+    #[test]
+    fn sight_is_drawable() {
+        let mut source = Sight::headless(2, 2);
+        source.put_pixel(1, 0, Color::RED);
+        source.put_pixel(0, 1, Color::GREEN);
+
+        let mut target = Sight::headless(6, 6);
+        target.draw_image(&source, 3, 3);
+        assert_eq!(px(&target, 4, 3), Color::RED);
+        assert_eq!(px(&target, 3, 4), Color::GREEN);
+        assert_eq!(px(&target, 3, 3), Color::TRANSPARENT);
+
+        target.draw_image_scaled(&source, Rect::new(0, 0, 4, 4));
+        assert_eq!(px(&target, 3, 1), Color::RED);
+        assert_eq!(px(&target, 1, 2), Color::GREEN);
+        assert_eq!(px(&target, 0, 0), Color::TRANSPARENT);
+    }
+
+    #[test]
+    fn blended_rounded_rect_blends_once() {
+        let mut sight = Sight::headless(20, 12);
+        sight.clear(Color::BLACK);
+        let color = Color::WHITE.with_alpha(128);
+        sight.fill_rounded_rect_blended(Rect::new(2, 2, 16, 8), 4, color);
+
+        let full = color.blend(Color::BLACK);
+        assert_eq!(px(&sight, 10, 6), full);
+        assert_eq!(px(&sight, 10, 2), full);
+        assert!(sight
+            .buffer()
+            .iter()
+            .all(|&p| Color::from_u32(p).r <= full.r));
+        let r = px(&sight, 3, 3).r;
+        assert!(r > 0 && r < full.r);
+    }
+
+    #[test]
+    fn with_opacity_blends_opaque_draws() {
+        let mut sight = Sight::headless(4, 4);
+        sight.clear(Color::BLACK);
+        sight.with_opacity(0.5, |sight| {
+            sight.fill_rect(Rect::new(0, 0, 2, 2), Color::WHITE);
+            sight.with_opacity(0.5, |sight| sight.put_pixel(3, 0, Color::WHITE));
+        });
+        assert_eq!(px(&sight, 0, 0), Color::rgb(127, 127, 127));
+        assert_eq!(px(&sight, 3, 0), Color::rgb(63, 63, 63));
+
+        sight.fill_rect(Rect::new(2, 2, 2, 2), Color::WHITE);
+        assert_eq!(px(&sight, 3, 3), Color::WHITE);
+    }
+
+    #[test]
+    fn contrast_ratio_and_text_color() {
+        let ratio = Color::WHITE.contrast_ratio(Color::BLACK);
+        assert!((ratio - 21.0).abs() < 0.01, "{ratio}");
+        assert_eq!(Color::BLACK.contrast_ratio(Color::WHITE), ratio);
+        assert!((Color::RED.contrast_ratio(Color::RED) - 1.0).abs() < 1e-6);
+
+        assert_eq!(Color::rgb(20, 30, 60).best_text_color(), Color::WHITE);
+        assert_eq!(Color::rgb(240, 230, 100).best_text_color(), Color::BLACK);
+    }
+
+    #[test]
+    fn filled_circle_has_fill_stroke_and_soft_edge() {
+        let mut sight = Sight::headless(24, 24);
+        sight.clear(Color::BLACK);
+        sight.draw_filled_circle(12.0, 12.0, 9.0, Color::BLUE, Color::WHITE, 2);
+
+        assert_eq!(px(&sight, 12, 12), Color::BLUE);
+        assert_eq!(px(&sight, 16, 12), Color::BLUE);
+        assert_eq!(px(&sight, 12, 4), Color::WHITE);
+        assert_eq!(px(&sight, 20, 12), Color::WHITE);
+        let edge = px(&sight, 19, 18);
+        assert!(edge.r > 0 && edge.r < 255 && edge.r == edge.b, "{edge:?}");
+        assert_eq!(px(&sight, 0, 0), Color::BLACK);
+    }
+
+    #[test]
+    fn rounded_polygon_skips_vertices() {
+        let points = [(2, 2), (26, 2), (26, 16), (10, 16), (4, 22)].map(|(x, y)| Point::new(x, y));
+        let mut sight = Sight::headless(30, 24);
+        sight.clear(Color::BLACK);
+        sight.draw_polygon_rounded(&points, 5, Color::WHITE);
+        assert_eq!(px(&sight, 2, 2), Color::BLACK);
+        assert_eq!(px(&sight, 26, 2), Color::BLACK);
+        assert_eq!(px(&sight, 15, 2), Color::WHITE);
+        assert_eq!(px(&sight, 26, 9), Color::WHITE);
+
+        sight.clear(Color::BLACK);
+        sight.fill_polygon_rounded(&points, 5, Color::WHITE);
+        assert_eq!(px(&sight, 2, 2), Color::BLACK);
+        assert_eq!(px(&sight, 15, 8), Color::WHITE);
+
+        sight.clear(Color::BLACK);
+        sight.draw_polygon_rounded(&points, 0, Color::WHITE);
+        assert_eq!(px(&sight, 2, 2), Color::WHITE);
+    }
+
+    #[test]
+    fn line_lights_exact_pixel_span() {
+        let mut sight = Sight::headless(16, 4);
+        sight.clear(Color::BLACK);
+        sight.draw_line(Point::new(0, 1), Point::new(10, 1), Color::WHITE);
+        let lit: Vec<i32> = (0..16)
+            .filter(|&x| px(&sight, x, 1) != Color::BLACK)
+            .collect();
+        assert_eq!(lit, (0..=10).collect::<Vec<_>>());
+        assert!(lit.iter().all(|&x| px(&sight, x, 1) == Color::WHITE));
+        for y in [0, 2, 3] {
+            assert!((0..16).all(|x| px(&sight, x, y) == Color::BLACK));
+        }
+
+        sight.clear(Color::BLACK);
+        sight.draw_line(
+            Point::new(3, 3),
+            Point::new(3, 3),
+            Color::WHITE.with_alpha(128),
+        );
+        assert_eq!(px(&sight, 3, 3).r, 128);
+    }
+
+    #[test]
+    fn wu_line_handles_negative_coordinates() {
+        let mut covered = Vec::new();
+        wu_line(-4.0, -2.0, 4.0, 2.0, |x, y, alpha| {
+            covered.push((x, y, alpha))
+        });
+        assert!(covered.contains(&(-4, -2, 1.0)));
+        assert!(covered.contains(&(4, 2, 1.0)));
+        assert!(covered.contains(&(0, 0, 1.0)));
+        assert!(covered
+            .iter()
+            .all(|&(_, _, alpha)| alpha > 0.0 && alpha <= 1.0));
+        for x in -4..=4 {
+            let column: f32 = covered.iter().filter(|c| c.0 == x).map(|c| c.2).sum();
+            assert!((column - 1.0).abs() < 1e-4, "column {x} = {column}");
+        }
+    }
+
+    #[test]
+    fn gradient_over_darkens_toward_opaque_end() {
+        let mut sight = Sight::headless(2, 10);
+        sight.clear(Color::WHITE);
+        let rect = Rect::new(0, 0, 2, 10);
+        sight.fill_gradient_v_over(rect, Color::BLACK.with_alpha(0), Color::BLACK);
+
+        assert_eq!(px(&sight, 0, 0), Color::WHITE);
+        assert!(px(&sight, 0, 9).r < 40);
+        for y in 1..10 {
+            assert!(px(&sight, 1, y).r <= px(&sight, 1, y - 1).r);
+        }
+
+        let mut sight = Sight::headless(10, 1);
+        sight.clear(Color::WHITE);
+        let rect = Rect::new(0, 0, 10, 1);
+        sight.fill_gradient_h_over(rect, Color::BLACK, Color::BLACK.with_alpha(0));
+        assert_eq!(px(&sight, 0, 0), Color::BLACK);
+        assert!(px(&sight, 9, 0).r > 220);
+    }
+
+    #[test]
+    fn alpha_gradient_fades_over_background() {
+        let mut sight = Sight::headless(10, 1);
+        sight.clear(Color::BLUE);
+        let (clear_red, red) = (Color::rgba(255, 0, 0, 0), Color::rgba(255, 0, 0, 255));
+        sight.fill_gradient_h(Rect::new(0, 0, 10, 1), clear_red, red);
+
+        assert_eq!(px(&sight, 0, 0), Color::BLUE);
+        let right = px(&sight, 9, 0);
+        assert!(right.r > 220 && right.b < 40, "{right:?}");
+        for x in 1..10 {
+            let (prev, cur) = (px(&sight, x - 1, 0), px(&sight, x, 0));
+            assert!(cur.r >= prev.r && cur.b <= prev.b);
+            assert_eq!(cur.a, 255);
+        }
+    }
+
+    #[test]
+    fn rect_from_corners_and_center() {
+        let (a, b) = (Point::new(5, 9), Point::new(-3, 2));
+        let expected = Rect::new(-3, 2, 8, 7);
+        assert_eq!(Rect::from_corners(a, b), expected);
+        assert_eq!(Rect::from_corners(b, a), expected);
+        assert_eq!(
+            Rect::from_corners(Point::new(5, 2), Point::new(-3, 9)),
+            expected
+        );
+
+        assert_eq!(
+            Rect::from_center(Point::new(10, 10), 4, 6),
+            Rect::new(8, 7, 4, 6)
+        );
+        assert_eq!(
+            Rect::from_center(Point::new(0, 0), 3, 1),
+            Rect::new(-1, 0, 3, 1)
+        );
+    }
+
+    #[test]
+    fn arc_gauge_is_solid_with_soft_edges() {
+        let mut sight = Sight::headless(31, 31);
+        sight.clear(Color::BLACK);
+        let center = Point::new(15, 15);
+        sight.draw_arc_gauge(center, 12, 4, 0.0, core::f32::consts::PI, Color::WHITE);
+
+        for y in 16..31 {
+            for x in 0..31 {
+                let dist = Point::new(x, y).distance_to(center);
+                let angle = atan2f((y - 15) as f32, (x - 15) as f32);
+                if (9.0..=11.0).contains(&dist) && angle > 0.2 && angle < 2.9 {
+                    assert_eq!(px(&sight, x, y), Color::WHITE, "({x}, {y})");
+                }
+            }
+        }
+        for (x, y) in [(15, 27), (15, 23)] {
+            let r = px(&sight, x, y).r;
+            assert!(r > 0 && r < 255, "({x}, {y}) = {r}");
+        }
+        assert_eq!(px(&sight, 15, 5), Color::BLACK);
+        assert_eq!(px(&sight, 15, 15), Color::BLACK);
+    }
+
+    #[test]
+    fn nine_slice_keeps_corners_and_stretches_center() {
+        let source = |x: u32, y: u32| Color::rgb(x as u8 * 60 + 1, y as u8 * 60 + 1, 255);
+        let mut image = bmp::BmpImage::new(4, 4);
+        for y in 0..4 {
+            for x in 0..4 {
+                image.set_pixel(x, y, source(x, y));
+            }
+        }
+
+        let mut sight = Sight::headless(12, 10);
+        sight.draw_nine_slice(&image, (1, 1, 1, 1), Rect::new(1, 1, 10, 8));
+        assert_eq!(px(&sight, 1, 1), source(0, 0));
+        assert_eq!(px(&sight, 10, 1), source(3, 0));
+        assert_eq!(px(&sight, 1, 8), source(0, 3));
+        assert_eq!(px(&sight, 10, 8), source(3, 3));
+        for y in 2..8 {
+            for x in 2..10 {
+                let c = px(&sight, x, y);
+                assert!(
+                    [61, 121].contains(&c.r) && [61, 121].contains(&c.g),
+                    "({x}, {y})"
+                );
+            }
+        }
+        assert_eq!(px(&sight, 5, 1).g, 1);
+        assert_eq!(px(&sight, 0, 0), Color::TRANSPARENT);
+
+        let mut untouched = Sight::headless(12, 10);
+        untouched.draw_nine_slice(&image, (u32::MAX, 2, 1, 1), Rect::new(1, 1, 10, 8));
+        untouched.draw_nine_slice(&image, (3, 1, 2, 1), Rect::new(1, 1, 10, 8));
+        assert!(untouched.buffer().iter().all(|&p| p == 0));
+    }
+
+    #[test]
+    fn bezier_flattening_adapts_to_curvature() {
+        let flatten = |p: [(f32, f32); 4]| {
+            let mut points = alloc::vec![p[0]];
+            flatten_cubic(p, 0.25, 0, &mut points);
+            points
+        };
+
+        let straight = flatten([(0.0, 0.0), (10.0, 0.1), (20.0, -0.1), (30.0, 0.0)]);
+        let curved = flatten([(0.0, 0.0), (0.0, 30.0), (30.0, 30.0), (30.0, 0.0)]);
+        assert_eq!(straight.len(), 2);
+        assert!(curved.len() > 8, "{}", curved.len());
+        assert_eq!(curved.first(), Some(&(0.0, 0.0)));
+        assert_eq!(curved.last(), Some(&(30.0, 0.0)));
+
+        let mut sight = Sight::headless(40, 24);
+        sight.clear(Color::BLACK);
+        let (start, end) = (Point::new(2, 20), Point::new(37, 3));
+        sight.draw_cubic_bezier_aa(
+            start,
+            Point::new(10, -10),
+            Point::new(30, 40),
+            end,
+            0.25,
+            Color::WHITE,
+        );
+        assert_eq!(px(&sight, start.x, start.y), Color::WHITE);
+        assert_eq!(px(&sight, end.x, end.y), Color::WHITE);
+    }
+
+    #[test]
+    fn histogram_of_solid_region() {
+        let mut sight = Sight::headless(8, 8);
+        sight.clear(Color::BLACK);
+        let color = Color::rgb(10, 20, 30);
+        let region = Rect::new(2, 2, 4, 4);
+        sight.fill_rect(region, color);
+
+        let histogram = sight.histogram(region);
+        for (channel, value) in [(0, 10), (1, 20), (2, 30)] {
+            assert_eq!(histogram[channel][value], 16);
+            assert_eq!(histogram[channel].iter().filter(|&&n| n > 0).count(), 1);
+        }
+        assert_eq!(sight.average_color(region), color);
+        assert_eq!(
+            sight.average_color(Rect::new(20, 20, 4, 4)),
+            Color::TRANSPARENT
+        );
+        assert_eq!(
+            sight.histogram(Rect::new(-5, -5, 100, 100))[0]
+                .iter()
+                .sum::<u32>(),
+            64
+        );
+    }
+
+    #[test]
+    fn color_blend_over_background() {
+        let background = Color::rgb(0, 0, 200);
+        assert_eq!(Color::RED.blend(background), Color::RED);
+        assert_eq!(Color::RED.with_alpha(0).blend(background), background);
+        assert_eq!(
+            Color::rgba(255, 0, 0, 51).blend(background),
+            Color::rgb(51, 0, 160)
+        );
+        assert_eq!(
+            Color::rgba(255, 0, 0, 128).blend(Color::TRANSPARENT),
+            Color::rgba(255, 0, 0, 128)
+        );
     }
 }
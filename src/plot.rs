@@ -0,0 +1,134 @@
+use crate::{Color, Gradient, Point, Rect, Sight};
+use libm::roundf;
+
+impl Sight {
+    pub fn draw_axes(&mut self, rect: Rect, color: Color) {
+        if rect.width == 0 || rect.height == 0 {
+            return;
+        }
+
+        let right = rect.x + rect.width as i32 - 1;
+        let bottom = rect.y + rect.height as i32 - 1;
+
+        self.draw_vline(rect.x, rect.y, bottom, color);
+        self.draw_hline(rect.x, right, bottom, color);
+    }
+
+    pub fn plot_series(&mut self, rect: Rect, data: &[f32], color: Color) {
+        if data.is_empty() || rect.width == 0 || rect.height == 0 {
+            return;
+        }
+
+        let min = data.iter().copied().fold(f32::INFINITY, f32::min);
+        let max = data.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+
+        if data.len() == 1 {
+            let p = series_point(rect, 0, 1, data[0], min, max);
+            self.draw_point(p, 1, color);
+            return;
+        }
+
+        for i in 0..data.len() - 1 {
+            let p1 = series_point(rect, i, data.len(), data[i], min, max);
+            let p2 = series_point(rect, i + 1, data.len(), data[i + 1], min, max);
+            self.draw_line(p1, p2, color);
+        }
+    }
+
+    pub fn draw_heatmap(
+        &mut self,
+        rect: Rect,
+        data: &[f32],
+        cols: u32,
+        gradient: &Gradient,
+    ) -> Result<(), &'static str> {
+        if cols == 0 || data.is_empty() || !data.len().is_multiple_of(cols as usize) {
+            return Err("Heatmap data does not fill a whole number of rows");
+        }
+
+        let rows = (data.len() / cols as usize) as u32;
+        let min = data.iter().copied().fold(f32::INFINITY, f32::min);
+        let max = data.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+        let range = max - min;
+
+        for dy in 0..rect.height {
+            let row = (dy as u64 * rows as u64 / rect.height as u64) as usize;
+            for dx in 0..rect.width {
+                let col = (dx as u64 * cols as u64 / rect.width as u64) as usize;
+                let value = data[row * cols as usize + col];
+                let t = if range > 0.0 {
+                    (value - min) / range
+                } else {
+                    0.0
+                };
+                self.put_pixel_clipped(rect.x + dx as i32, rect.y + dy as i32, gradient.sample(t));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn series_point(rect: Rect, index: usize, count: usize, value: f32, min: f32, max: f32) -> Point {
+    let span_x = rect.width as i32 - 1;
+    let span_y = (rect.height - 1) as f32;
+
+    let x = if count > 1 {
+        rect.x + (index as i32 * span_x) / (count as i32 - 1)
+    } else {
+        rect.x
+    };
+
+    let range = max - min;
+    let t = if range > 0.0 {
+        (value - min) / range
+    } else {
+        0.5
+    };
+    let y = rect.y + roundf((1.0 - t) * span_y) as i32;
+
+    Point::new(x, y)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::px;
+
+    #[test]
+    fn series_spans_rect_edges() {
+        let rect = Rect::new(2, 2, 10, 6);
+        assert_eq!(series_point(rect, 0, 3, 1.0, 1.0, 3.0), Point::new(2, 7));
+        assert_eq!(series_point(rect, 2, 3, 3.0, 1.0, 3.0), Point::new(11, 2));
+
+        let mut sight = Sight::headless(14, 10);
+        sight.clear(Color::BLACK);
+        sight.plot_series(rect, &[1.0, 3.0, 2.0], Color::WHITE);
+        assert_eq!(px(&sight, 2, 7), Color::WHITE);
+        assert_eq!(px(&sight, 11, 5), Color::WHITE);
+        for y in 0..10 {
+            for x in [0, 1, 12, 13] {
+                assert_eq!(px(&sight, x, y), Color::BLACK);
+            }
+        }
+    }
+
+    #[test]
+    fn heatmap_maps_cells_to_gradient() {
+        let gradient = Gradient::new(&[(0.0, Color::BLUE), (1.0, Color::RED)]);
+        let mut sight = Sight::headless(4, 4);
+        let rect = Rect::new(0, 0, 4, 4);
+        assert!(sight
+            .draw_heatmap(rect, &[1.0, 2.0, 3.0], 2, &gradient)
+            .is_err());
+        assert!(sight.draw_heatmap(rect, &[1.0], 0, &gradient).is_err());
+
+        sight
+            .draw_heatmap(rect, &[0.0, 2.0, 1.0, 0.0], 2, &gradient)
+            .unwrap();
+        assert_eq!(px(&sight, 0, 0), Color::BLUE);
+        assert_eq!(px(&sight, 3, 0), Color::RED);
+        assert_eq!(px(&sight, 1, 3), Color::BLUE.lerp(Color::RED, 0.5));
+        assert_eq!(px(&sight, 3, 3), Color::BLUE);
+    }
+}
@@ -0,0 +1,104 @@
+use crate::{Color, Point, Rect, Sight};
+
+impl Sight {
+    pub fn draw_checkbox(&mut self, rect: Rect, checked: bool, color: Color) {
+        if rect.width == 0 || rect.height == 0 {
+            return;
+        }
+
+        let radius = (rect.width.min(rect.height) / 6) as i32;
+        self.draw_rounded_rect_thick(rect, radius, 1, color);
+
+        if checked {
+            let at = |fx: f32, fy: f32| {
+                Point::new(
+                    rect.x + (rect.width as f32 * fx) as i32,
+                    rect.y + (rect.height as f32 * fy) as i32,
+                )
+            };
+            let (start, corner, end) = (at(0.22, 0.52), at(0.42, 0.72), at(0.78, 0.3));
+            self.draw_line_capped(start, corner, color, true);
+            self.draw_line_capped(corner, end, color, true);
+        }
+    }
+
+    pub fn draw_radio(&mut self, center: Point, radius: i32, selected: bool, color: Color) {
+        if radius <= 0 {
+            return;
+        }
+
+        self.draw_ellipse_aa(center, radius, radius, color);
+
+        if selected {
+            let dot = radius as f32 * 0.5;
+            self.fill_disk_subpixel(center.x as f32, center.y as f32, dot, color);
+        }
+    }
+
+    pub fn draw_toggle(&mut self, rect: Rect, on: bool, track: Color, knob: Color) {
+        if rect.width == 0 || rect.height == 0 {
+            return;
+        }
+
+        let half = rect.height as f32 / 2.0;
+        self.fill_rounded_rect_blended(rect, rect.height as i32 / 2, track);
+
+        // fill_disk_subpixel measures from pixel corners, the track from pixel centers.
+        let knob_x = if on {
+            rect.x as f32 + rect.width as f32 - half - 0.5
+        } else {
+            rect.x as f32 + half - 0.5
+        };
+        let knob_y = rect.y as f32 + half - 0.5;
+        let knob_radius = (half - 2.0).max(1.0);
+        self.fill_disk_subpixel(knob_x, knob_y, knob_radius, knob);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::px;
+
+    #[test]
+    fn checkbox_draws_check_mark_when_checked() {
+        let mut sight = Sight::headless(16, 16);
+        sight.clear(Color::BLACK);
+        sight.draw_checkbox(Rect::new(1, 1, 14, 14), false, Color::WHITE);
+        assert_eq!(px(&sight, 6, 11), Color::BLACK);
+        assert_eq!(px(&sight, 8, 1), Color::WHITE);
+
+        sight.draw_checkbox(Rect::new(1, 1, 14, 14), true, Color::WHITE);
+        assert_eq!(px(&sight, 6, 11), Color::WHITE);
+        assert_eq!(px(&sight, 4, 8), Color::WHITE);
+    }
+
+    #[test]
+    fn radio_dot_marks_selection() {
+        let mut sight = Sight::headless(16, 16);
+        sight.clear(Color::BLACK);
+        sight.draw_radio(Point::new(8, 8), 6, false, Color::WHITE);
+        assert_eq!(px(&sight, 8, 8), Color::BLACK);
+        sight.draw_radio(Point::new(8, 8), 6, true, Color::WHITE);
+        assert_eq!(px(&sight, 8, 8), Color::WHITE);
+    }
+
+    #[test]
+    fn toggle_knob_follows_state() {
+        let track = Color::rgb(0, 0, 200);
+        let toggle = |on| {
+            let mut sight = Sight::headless(24, 10);
+            sight.clear(Color::BLACK);
+            sight.draw_toggle(Rect::new(0, 0, 24, 10), on, track, Color::WHITE);
+            sight
+        };
+
+        let off = toggle(false);
+        assert_eq!(px(&off, 5, 5), Color::WHITE);
+        assert_eq!(px(&off, 19, 5), track);
+
+        let on = toggle(true);
+        assert_eq!(px(&on, 5, 5), track);
+        assert_eq!(px(&on, 19, 5), Color::WHITE);
+    }
+}